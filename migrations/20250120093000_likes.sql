@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE quotes ADD COLUMN IF NOT EXISTS likes INT NOT NULL DEFAULT 0;
+ALTER TABLE cursors ADD COLUMN IF NOT EXISTS sort TEXT NOT NULL DEFAULT 'created_at';
\ No newline at end of file
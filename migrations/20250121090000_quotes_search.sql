@@ -0,0 +1,3 @@
+-- Add migration script here
+CREATE INDEX IF NOT EXISTS quotes_search_idx ON quotes
+USING GIN (to_tsvector('english', author || ' ' || quote));
\ No newline at end of file
@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE cursors ADD COLUMN IF NOT EXISTS direction TEXT NOT NULL DEFAULT 'asc';
\ No newline at end of file
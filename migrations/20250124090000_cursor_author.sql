@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE cursors ADD COLUMN IF NOT EXISTS author TEXT;
\ No newline at end of file
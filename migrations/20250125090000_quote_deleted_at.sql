@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE quotes ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE cursors ADD COLUMN IF NOT EXISTS created_after TIMESTAMPTZ;
+ALTER TABLE cursors ADD COLUMN IF NOT EXISTS created_before TIMESTAMPTZ;
\ No newline at end of file
@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE cursors ADD COLUMN IF NOT EXISTS page_size INT NOT NULL DEFAULT 3;
\ No newline at end of file
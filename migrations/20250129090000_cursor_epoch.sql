@@ -0,0 +1,6 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS cursor_epoch
+(
+    id INT PRIMARY KEY,
+    epoch BIGINT NOT NULL
+);
\ No newline at end of file
@@ -0,0 +1,117 @@
+use axum::{
+    extract::{FromRef, State},
+    response::Response,
+    routing::post,
+};
+use sqlx::PgPool;
+
+use crate::{
+    auth::{AdminAuth, AdminTokenHash},
+    day12, day9,
+};
+
+#[derive(Clone)]
+pub struct AdminHandles {
+    pub board: day12::SharedState,
+    pub pool: PgPool,
+    pub milk: day9::LimiterState,
+    pub milk_config: day9::RateLimiterConfig,
+}
+
+#[derive(Clone)]
+struct AppState {
+    handles: AdminHandles,
+    // Without a configured hash every admin request is rejected
+    token_hash: AdminTokenHash,
+}
+
+impl FromRef<AppState> for AdminTokenHash {
+    fn from_ref(state: &AppState) -> Self {
+        state.token_hash.clone()
+    }
+}
+
+pub fn router(handles: AdminHandles) -> axum::Router {
+    router_with_hash(handles, AdminTokenHash::from_env())
+}
+
+fn router_with_hash(handles: AdminHandles, token_hash: AdminTokenHash) -> axum::Router {
+    axum::Router::new()
+        .route("/reset", post(reset))
+        .with_state(AppState {
+            handles,
+            token_hash,
+        })
+}
+
+async fn reset(_: AdminAuth, State(state): State<AppState>) -> Result<String, Response> {
+    let handles = state.handles;
+    *handles.milk.lock().unwrap() = handles.milk_config.build();
+    day12::reset_board(&handles.board, &handles.pool).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use axum::{
+        body::Body,
+        extract::Request,
+        http::{header::AUTHORIZATION, StatusCode},
+    };
+    use tower::Service;
+
+    fn post(uri: &str, token: Option<&str>) -> Request {
+        let mut request = Request::builder().method("POST").uri(uri);
+        if let Some(token) = token {
+            request = request.header(AUTHORIZATION, format!("Bearer {token}"));
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_reset(pool: PgPool) {
+        let milk_config = day9::RateLimiterConfig {
+            max: 1,
+            initial: 1,
+            interval_ms: 60_000,
+        };
+        let handles = AdminHandles {
+            board: day12::shared_board(&pool).await,
+            pool: pool.clone(),
+            milk: Arc::new(Mutex::new(milk_config.build())),
+            milk_config,
+        };
+        let mut app = axum::Router::new()
+            .nest(
+                "/9",
+                day9::router_with_limiter(milk_config, handles.milk.clone()),
+            )
+            .nest("/12", day12::router_with_board(pool, handles.board.clone()))
+            .nest(
+                "/admin",
+                router_with_hash(handles.clone(), AdminTokenHash::from_token("secret")),
+            );
+
+        app.call(post("/12/place/cookie/1", None)).await.unwrap();
+        app.call(post("/9/milk", None)).await.unwrap();
+        let response = app.call(post("/9/milk", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let response = app.call(post("/admin/reset", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app.call(post("/admin/reset", Some("wrong"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app
+            .call(post("/admin/reset", Some("secret")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!handles.board.lock().await.to_string().contains('🍪'));
+        let response = app.call(post("/9/milk", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
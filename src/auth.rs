@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use argon2::{
+    password_hash::{PasswordHashString, SaltString},
+    Argon2, PasswordHasher, PasswordVerifier,
+};
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::{header::AUTHORIZATION, request::Parts, StatusCode},
+};
+use tracing::{error, warn};
+
+// Argon2 PHC string for the admin token, so the token itself never has to be stored
+#[derive(Clone, Default)]
+pub struct AdminTokenHash(Option<Arc<PasswordHashString>>);
+
+impl AdminTokenHash {
+    pub fn from_env() -> Self {
+        if let Ok(hash) = std::env::var("ADMIN_TOKEN_HASH") {
+            return Self::parse(&hash)
+                .inspect_err(|error| error!(%error, "ADMIN_TOKEN_HASH was not a valid argon2 hash"))
+                .unwrap_or_default();
+        }
+        // Deployments from before hashed tokens still set the plaintext ADMIN_TOKEN
+        match std::env::var("ADMIN_TOKEN") {
+            Ok(token) => {
+                warn!("ADMIN_TOKEN is deprecated, set ADMIN_TOKEN_HASH to its argon2 hash instead");
+                Self::from_token(&token)
+            }
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn from_token(token: &str) -> Self {
+        let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>())
+            .expect("16 bytes must fit in a salt");
+        let hash = Argon2::default()
+            .hash_password(token.as_bytes(), &salt)
+            .expect("argon2 hashing with default params must succeed");
+        Self(Some(Arc::new(hash.serialize())))
+    }
+
+    pub fn parse(hash: &str) -> Result<Self, argon2::password_hash::Error> {
+        let hash = PasswordHashString::new(hash)?;
+        Ok(Self(Some(Arc::new(hash))))
+    }
+
+    // The digest comparison inside verify_password is constant time
+    fn verify(&self, token: &str) -> bool {
+        let Some(hash) = &self.0 else {
+            return false;
+        };
+        Argon2::default()
+            .verify_password(token.as_bytes(), &hash.password_hash())
+            .is_ok()
+    }
+}
+
+// Requires an `Authorization: Bearer <token>` header matching the admin token hash,
+// or the deprecated `X-Admin-Token` header used before bearer tokens
+pub struct AdminAuth;
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AdminAuth
+where
+    AdminTokenHash: FromRef<S>,
+{
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let bearer = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        let token = match bearer {
+            Some(token) => token,
+            None => {
+                let token = parts
+                    .headers
+                    .get("X-Admin-Token")
+                    .and_then(|value| value.to_str().ok())
+                    .ok_or(StatusCode::UNAUTHORIZED)?;
+                warn!("X-Admin-Token is deprecated, send Authorization: Bearer instead");
+                token
+            }
+        };
+        if AdminTokenHash::from_ref(state).verify(token) {
+            Ok(AdminAuth)
+        } else {
+            Err(StatusCode::UNAUTHORIZED)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{body::Body, extract::Request, routing::get};
+    use tower::ServiceExt;
+
+    async fn protected(_: AdminAuth) -> &'static str {
+        "ok"
+    }
+
+    #[tokio::test]
+    async fn test_admin_auth() {
+        let app = axum::Router::new()
+            .route("/", get(protected))
+            .with_state(AdminTokenHash::from_token("secret"));
+        let status = |token: Option<&str>| {
+            let mut request = Request::builder().uri("/");
+            if let Some(token) = token {
+                request = request.header(AUTHORIZATION, format!("Bearer {token}"));
+            }
+            let request = request.body(Body::empty()).unwrap();
+            let app = app.clone();
+            async move { app.oneshot(request).await.unwrap().status() }
+        };
+
+        assert_eq!(status(Some("secret")).await, StatusCode::OK);
+        assert_eq!(status(Some("wrong")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(None).await, StatusCode::UNAUTHORIZED);
+
+        let legacy = |token: &str| {
+            Request::builder()
+                .uri("/")
+                .header("X-Admin-Token", token)
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = app.clone().oneshot(legacy("secret")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.clone().oneshot(legacy("wrong")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let app = axum::Router::new()
+            .route("/", get(protected))
+            .with_state(AdminTokenHash::default());
+        let request = Request::builder()
+            .uri("/")
+            .header(AUTHORIZATION, "Bearer secret")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn test_parse_hash() {
+        let hash = AdminTokenHash::from_token("secret");
+        let phc = hash.0.as_ref().unwrap().to_string();
+        assert!(AdminTokenHash::parse(&phc).unwrap().verify("secret"));
+        assert!(AdminTokenHash::parse("not a hash").is_err());
+    }
+}
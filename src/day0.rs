@@ -1,18 +1,52 @@
+use std::sync::LazyLock;
+
 use axum::{
-    http::{header, StatusCode},
-    response::IntoResponse,
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
 };
+use tracing::error;
+
+const DEFAULT_SEEK_URL: &str = "https://www.youtube.com/watch?v=9Gc4QTqslN4";
+
+static SEEK_LOCATION: LazyLock<Option<HeaderValue>> =
+    LazyLock::new(|| seek_location(std::env::var("CCH_SEEK_URL").ok()));
 
 pub async fn hello_bird() -> &'static str {
     "Hello, bird!"
 }
 
-pub async fn the_word() -> impl IntoResponse {
-    (
-        StatusCode::FOUND,
-        [(
-            header::LOCATION,
-            "https://www.youtube.com/watch?v=9Gc4QTqslN4",
-        )],
-    )
+pub async fn the_word() -> Response {
+    match SEEK_LOCATION.as_ref() {
+        Some(location) => {
+            (StatusCode::FOUND, [(header::LOCATION, location.clone())]).into_response()
+        }
+        None => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+fn seek_location(configured: Option<String>) -> Option<HeaderValue> {
+    match configured {
+        Some(url) => HeaderValue::from_str(&url)
+            .inspect_err(|err| error!(%err, "CCH_SEEK_URL is not a valid header value"))
+            .ok(),
+        None => Some(HeaderValue::from_static(DEFAULT_SEEK_URL)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seek_location_default() {
+        let location = seek_location(None).unwrap();
+        assert_eq!(location, "https://www.youtube.com/watch?v=9Gc4QTqslN4");
+    }
+
+    #[test]
+    fn test_seek_location_override() {
+        let location = seek_location(Some("http://localhost:8000/demo".to_string())).unwrap();
+        assert_eq!(location, "http://localhost:8000/demo");
+        assert!(seek_location(Some("not\na header".to_string())).is_none());
+    }
 }
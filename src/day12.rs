@@ -1,57 +1,1089 @@
 use std::sync::{Arc, Mutex};
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    async_trait,
+    extract::{rejection::JsonRejection, FromRef, FromRequestParts, Path, Query, State},
+    http::{request::Parts, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     routing::{get, post},
+    Json,
 };
-use game::{GameBoard, GamePiece};
+use game::{BoardStyle, GameBoard, GameError, GamePiece, GameState, GameStats, Placement};
+use serde::{Deserialize, Serialize};
+use snapshot::Snapshots;
+use sqlx::PgPool;
+use tracing::error;
 
 mod game;
+mod snapshot;
 
-type SharedState = Arc<Mutex<GameBoard>>;
+// An async mutex so the lock can be held while a change is written to the database
+pub type SharedState = Arc<tokio::sync::Mutex<GameBoard>>;
+type SharedSnapshots = Arc<Mutex<Snapshots>>;
 
-pub fn router() -> axum::Router {
+// The board is persisted as a single row so it survives restarts
+const BOARD_ID: i32 = 1;
+
+#[derive(Clone)]
+struct AppState {
+    board: SharedState,
+    snapshots: SharedSnapshots,
+    pool: PgPool,
+}
+
+impl FromRef<AppState> for SharedState {
+    fn from_ref(state: &AppState) -> Self {
+        state.board.clone()
+    }
+}
+
+impl FromRef<AppState> for SharedSnapshots {
+    fn from_ref(state: &AppState) -> Self {
+        state.snapshots.clone()
+    }
+}
+
+impl FromRef<AppState> for PgPool {
+    fn from_ref(state: &AppState) -> Self {
+        state.pool.clone()
+    }
+}
+
+pub async fn shared_board(pool: &PgPool) -> SharedState {
+    let saved = load_board(pool)
+        .await
+        .inspect_err(|error| error!(%error, "failed to load saved board, starting a new one"))
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    Arc::new(tokio::sync::Mutex::new(saved))
+}
+
+pub fn router_with_board(pool: PgPool, shared: SharedState) -> axum::Router {
     axum::Router::new()
         .route("/board", get(board))
         .route("/place/:team/:column", post(place))
+        .route("/move", post(place_json))
+        .route("/legal-moves", get(legal_moves))
+        .route("/turn", get(turn))
+        .route("/suggest/:team", get(suggest))
+        .route("/status", get(status))
         .route("/random-board", get(randomize))
         .route("/reset", post(reset))
-        .with_state(Arc::new(Mutex::new(GameBoard::default())))
+        .route("/stats", get(stats))
+        .route("/reset-stats", post(reset_stats))
+        .route("/undo", post(undo))
+        .route("/history", get(history))
+        .route("/win-line", get(win_line))
+        .route("/snapshot", post(snapshot))
+        .route("/snapshot/:token", get(view_snapshot))
+        .with_state(AppState {
+            board: shared,
+            snapshots: SharedSnapshots::default(),
+            pool,
+        })
+}
+
+async fn load_board(pool: &PgPool) -> Result<Option<GameBoard>, sqlx::Error> {
+    let saved = sqlx::query!("SELECT board FROM board_state WHERE id = $1", BOARD_ID)
+        .fetch_optional(pool)
+        .await?;
+    Ok(saved.and_then(|saved| GameBoard::from_bytes(&saved.board)))
+}
+
+// Applies a change and persists it before releasing the board, so saves land in the
+// order the changes were made. A failed save puts the board back the way it was
+async fn update_board<T>(
+    state: &SharedState,
+    pool: &PgPool,
+    change: impl FnOnce(&mut GameBoard) -> Result<T, (StatusCode, String)>,
+) -> Result<(T, String), Response> {
+    let mut board = state.lock().await;
+    let before = board.clone();
+    let value = change(&mut board).map_err(IntoResponse::into_response)?;
+    if let Err(status) = save_board(pool, board.to_bytes()).await {
+        *board = before;
+        return Err(status.into_response());
+    }
+    Ok((value, board.to_string()))
+}
+
+async fn save_board(pool: &PgPool, board: Vec<u8>) -> Result<(), StatusCode> {
+    sqlx::query!(
+        "INSERT INTO board_state (id, board) VALUES ($1, $2)
+        ON CONFLICT (id) DO UPDATE SET board = EXCLUDED.board, updated_at = NOW()",
+        BOARD_ID,
+        board
+    )
+    .execute(pool)
+    .await
+    .inspect_err(|error| error!(%error, "failed to save board"))
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct BoardQuery {
+    #[serde(default)]
+    style: BoardStyle,
+}
+
+async fn board(Query(query): Query<BoardQuery>, State(state): State<SharedState>) -> String {
+    state.lock().await.render(query.style)
+}
+
+async fn legal_moves(State(state): State<SharedState>) -> Json<Vec<usize>> {
+    Json(state.lock().await.legal_columns())
+}
+
+async fn turn(State(state): State<SharedState>) -> Json<&'static str> {
+    Json(state.lock().await.next_turn().as_str())
+}
+
+async fn suggest(
+    Path(team): Path<String>,
+    State(state): State<SharedState>,
+) -> Result<Json<usize>, (StatusCode, &'static str)> {
+    let team: GamePiece = team
+        .parse()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "team must be 'cookie' or 'milk'"))?;
+    state
+        .lock()
+        .await
+        .suggest(team)
+        .map(Json)
+        .ok_or((StatusCode::CONFLICT, "game is over"))
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct GameStatus {
+    state: &'static str,
+    winner: Option<&'static str>,
+}
+
+impl From<GameState> for GameStatus {
+    fn from(state: GameState) -> Self {
+        match state {
+            GameState::Running => GameStatus {
+                state: "running",
+                winner: None,
+            },
+            GameState::Winner(winner) => GameStatus {
+                state: "winner",
+                winner: Some(winner.as_str()),
+            },
+            GameState::Draw => GameStatus {
+                state: "draw",
+                winner: None,
+            },
+        }
+    }
+}
+
+async fn status(State(state): State<SharedState>) -> Json<GameStatus> {
+    Json(state.lock().await.state.into())
+}
+
+struct Move {
+    team: GamePiece,
+    column: u8,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Move {
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Path((team, column)) = Path::<(String, String)>::from_request_parts(parts, state)
+            .await
+            .map_err(|_| (StatusCode::BAD_REQUEST, "expected /place/:team/:column"))?;
+        Move::parse(&team, column.parse().ok())
+    }
+}
+
+impl Move {
+    fn parse(team: &str, column: Option<u8>) -> Result<Self, (StatusCode, &'static str)> {
+        let team = team
+            .parse()
+            .map_err(|_| (StatusCode::BAD_REQUEST, "team must be 'cookie' or 'milk'"))?;
+        let column = column
+            .filter(|column| (1..=4).contains(column))
+            .ok_or((StatusCode::BAD_REQUEST, "column must be between 1 and 4"))?;
+        Ok(Move { team, column })
+    }
+}
+
+#[derive(Deserialize)]
+struct MoveBody {
+    team: String,
+    column: i64,
 }
 
-async fn board(State(state): State<SharedState>) -> String {
-    state.lock().unwrap().to_string()
+#[derive(Deserialize, Default)]
+struct PlaceQuery {
+    #[serde(default)]
+    describe: bool,
+    #[serde(default)]
+    enforce: bool,
 }
 
 async fn place(
-    Path((team, column)): Path<(GamePiece, u8)>,
+    next_move: Move,
+    Query(query): Query<PlaceQuery>,
+    State(state): State<SharedState>,
+    State(pool): State<PgPool>,
+) -> Response {
+    play(next_move, query, &state, &pool).await
+}
+
+async fn place_json(
+    Query(query): Query<PlaceQuery>,
     State(state): State<SharedState>,
+    State(pool): State<PgPool>,
+    body: Result<Json<MoveBody>, JsonRejection>,
+) -> Response {
+    let Ok(Json(body)) = body else {
+        return (StatusCode::BAD_REQUEST, "expected {\"team\", \"column\"}").into_response();
+    };
+    match Move::parse(&body.team, u8::try_from(body.column).ok()) {
+        Ok(next_move) => play(next_move, query, &state, &pool).await,
+        Err(rejection) => rejection.into_response(),
+    }
+}
+
+async fn play(
+    Move { team, column }: Move,
+    query: PlaceQuery,
+    state: &SharedState,
+    pool: &PgPool,
 ) -> Response {
-    if !(1..5).contains(&column) {
-        return StatusCode::BAD_REQUEST.into_response();
+    let played = update_board(state, pool, |state| {
+        if query.enforce && state.next_turn() != team {
+            return Err((
+                StatusCode::CONFLICT,
+                format!("it is {}'s turn", state.next_turn().as_str()),
+            ));
+        }
+        state.place(team, (column - 1) as usize).map_err(|error| {
+            let status = match error {
+                GameError::ColumnFull => StatusCode::CONFLICT,
+                GameError::GameOver => StatusCode::SERVICE_UNAVAILABLE,
+                GameError::InvalidColumn => StatusCode::BAD_REQUEST,
+            };
+            (status, state.to_string())
+        })
+    })
+    .await;
+    let (placement, board) = match played {
+        Ok(played) => played,
+        Err(response) => return response,
+    };
+
+    let mut response = board.into_response();
+    if let GameState::Draw = placement.state {
+        response
+            .headers_mut()
+            .insert("X-Game-Result", HeaderValue::from_static("draw"));
+    }
+    if query.describe {
+        let description = describe_move(team, column, &placement);
+        if let Ok(description) = HeaderValue::from_str(&description) {
+            response.headers_mut().insert("X-Move", description);
+        }
     }
-    let mut state = state.lock().unwrap();
-    if let game::GameState::Running = state.state {
-        if state.place(team, (column - 1) as usize).is_ok() {
-            state.to_string().into_response()
-        } else {
-            (StatusCode::SERVICE_UNAVAILABLE, state.to_string()).into_response()
+    response
+}
+
+fn describe_move(team: GamePiece, column: u8, placement: &Placement) -> String {
+    let outcome = match placement.state {
+        GameState::Running => "game running".to_string(),
+        GameState::Winner(winner) => format!("{} wins", winner.as_str()),
+        GameState::Draw => "game drawn".to_string(),
+    };
+    format!(
+        "{} placed in column {column}, landed row {}; {outcome}",
+        team.as_str(),
+        placement.row
+    )
+}
+
+#[derive(Deserialize)]
+struct RandomQuery {
+    seed: Option<u64>,
+}
+
+async fn randomize(
+    Query(query): Query<RandomQuery>,
+    State(state): State<SharedState>,
+    State(pool): State<PgPool>,
+) -> Result<String, Response> {
+    let ((), board) = update_board(&state, &pool, |state| {
+        // Without a seed the rng keeps running from its initial seed of 2024
+        if let Some(seed) = query.seed {
+            state.reseed(seed);
         }
-    } else {
-        (StatusCode::SERVICE_UNAVAILABLE, state.to_string()).into_response()
+        state.randomize();
+        Ok(())
+    })
+    .await?;
+    Ok(board)
+}
+
+async fn reset(
+    State(state): State<SharedState>,
+    State(pool): State<PgPool>,
+) -> Result<String, Response> {
+    reset_board(&state, &pool).await
+}
+
+pub async fn reset_board(state: &SharedState, pool: &PgPool) -> Result<String, Response> {
+    let ((), board) = update_board(state, pool, |state| {
+        state.reset();
+        Ok(())
+    })
+    .await?;
+    Ok(board)
+}
+
+async fn stats(State(state): State<SharedState>) -> Json<GameStats> {
+    Json(state.lock().await.stats())
+}
+
+async fn reset_stats(State(state): State<SharedState>) -> Json<GameStats> {
+    let mut state = state.lock().await;
+    state.reset_stats();
+    Json(state.stats())
+}
+
+async fn undo(
+    State(state): State<SharedState>,
+    State(pool): State<PgPool>,
+) -> Result<String, Response> {
+    let (_, board) = update_board(&state, &pool, |state| {
+        state
+            .undo()
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, String::new()))
+    })
+    .await?;
+    Ok(board)
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct PlayedMove {
+    team: &'static str,
+    column: usize,
+}
+
+async fn history(State(state): State<SharedState>) -> Json<Vec<PlayedMove>> {
+    let moves = state
+        .lock()
+        .await
+        .history()
+        .iter()
+        .map(|(team, column)| PlayedMove {
+            team: team.as_str(),
+            column: column + 1,
+        })
+        .collect();
+    Json(moves)
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct Cell {
+    row: usize,
+    col: usize,
+}
+
+async fn win_line(State(state): State<SharedState>) -> Result<Json<Vec<Cell>>, StatusCode> {
+    let state = state.lock().await;
+    if state.win_line().is_empty() {
+        return Err(StatusCode::NOT_FOUND);
     }
+    let cells = state
+        .win_line()
+        .iter()
+        .map(|&(row, col)| Cell { row, col })
+        .collect();
+    Ok(Json(cells))
 }
 
-async fn randomize(State(state): State<SharedState>) -> String {
-    let mut state = state.lock().unwrap();
-    state.randomize();
-    state.to_string()
+async fn snapshot(
+    State(state): State<SharedState>,
+    State(snapshots): State<SharedSnapshots>,
+) -> String {
+    let board = state.lock().await.to_string();
+    snapshots.lock().unwrap().insert(board)
+}
+
+async fn view_snapshot(
+    Path(token): Path<String>,
+    State(snapshots): State<SharedSnapshots>,
+) -> Result<String, StatusCode> {
+    snapshots
+        .lock()
+        .unwrap()
+        .get(&token)
+        .map(str::to_string)
+        .ok_or(StatusCode::NOT_FOUND)
 }
 
-async fn reset(State(state): State<SharedState>) -> String {
-    let mut state = state.lock().unwrap();
-    *state = GameBoard::default();
-    state.to_string()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{body::Body, extract::Request};
+    use http_body_util::BodyExt;
+    use tower::Service;
+
+    async fn router(pool: PgPool) -> axum::Router {
+        let saved = shared_board(&pool).await;
+        router_with_board(pool, saved)
+    }
+
+    async fn body_text(response: Response) -> String {
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_failed_save_rolls_back(pool: PgPool) {
+        let state = SharedState::default();
+        place(
+            Move {
+                team: GamePiece::Cookie,
+                column: 1,
+            },
+            Query(PlaceQuery::default()),
+            State(state.clone()),
+            State(pool.clone()),
+        )
+        .await;
+        let before = state.lock().await.to_string();
+
+        sqlx::query("DROP TABLE board_state")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let response = place(
+            Move {
+                team: GamePiece::Milk,
+                column: 2,
+            },
+            Query(PlaceQuery::default()),
+            State(state.clone()),
+            State(pool.clone()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(state.lock().await.to_string(), before);
+        assert_eq!(state.lock().await.history().len(), 1);
+
+        // A board that can't be loaded at startup falls back to a new game
+        let board = shared_board(&pool).await;
+        assert_eq!(
+            board.lock().await.to_string(),
+            GameBoard::default().to_string()
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_concurrent_saves_match_memory(pool: PgPool) {
+        let state = SharedState::default();
+        let moves = (0..12).map(|index| {
+            let next_move = Move {
+                team: if index % 2 == 0 {
+                    GamePiece::Cookie
+                } else {
+                    GamePiece::Milk
+                },
+                column: index % 4 + 1,
+            };
+            let (state, pool) = (state.clone(), pool.clone());
+            tokio::spawn(async move {
+                place(
+                    next_move,
+                    Query(PlaceQuery::default()),
+                    State(state),
+                    State(pool),
+                )
+                .await
+            })
+        });
+        for handle in moves.collect::<Vec<_>>() {
+            handle.await.unwrap();
+        }
+
+        let saved = load_board(&pool).await.unwrap().unwrap();
+        assert_eq!(saved.to_bytes(), state.lock().await.to_bytes());
+    }
+
+    #[sqlx::test]
+    async fn test_place_draw(pool: PgPool) {
+        use GamePiece::{Cookie, Milk};
+
+        let state = SharedState::default();
+        // Fill the board bottom-up in a pattern that never produces four in a row
+        let rows = [
+            [Cookie, Milk, Milk, Cookie],
+            [Milk, Cookie, Cookie, Milk],
+            [Cookie, Milk, Milk, Cookie],
+            [Milk, Cookie, Cookie, Milk],
+        ];
+        let mut moves = rows
+            .iter()
+            .flat_map(|row| row.iter().enumerate())
+            .map(|(column, &team)| Move {
+                team,
+                column: column as u8 + 1,
+            })
+            .collect::<Vec<_>>();
+        let last_move = moves.pop().unwrap();
+
+        for next_move in moves {
+            let response = place(
+                next_move,
+                Query(PlaceQuery::default()),
+                State(state.clone()),
+                State(pool.clone()),
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert!(response.headers().get("X-Game-Result").is_none());
+        }
+
+        let response = place(
+            last_move,
+            Query(PlaceQuery::default()),
+            State(state),
+            State(pool),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["X-Game-Result"], "draw");
+    }
+
+    #[sqlx::test]
+    async fn test_snapshot(pool: PgPool) {
+        let mut app = router(pool).await;
+        let post = |uri: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.call(post("/place/cookie/1")).await.unwrap();
+        let original = body_text(response).await;
+        let response = app.call(post("/snapshot")).await.unwrap();
+        let token = body_text(response).await;
+
+        app.call(post("/place/milk/2")).await.unwrap();
+        app.call(post("/reset")).await.unwrap();
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri(format!("/snapshot/{token}"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_text(response).await, original);
+    }
+
+    #[sqlx::test]
+    async fn test_place_describe(pool: PgPool) {
+        let state = SharedState::default();
+        let describe = || {
+            Query(PlaceQuery {
+                describe: true,
+                ..Default::default()
+            })
+        };
+
+        let milk = Move {
+            team: GamePiece::Milk,
+            column: 3,
+        };
+        let cookie = Move {
+            team: GamePiece::Cookie,
+            column: 3,
+        };
+        place(milk, describe(), State(state.clone()), State(pool.clone())).await;
+        let response = place(cookie, describe(), State(state), State(pool)).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()["X-Move"],
+            "cookie placed in column 3, landed row 2; game running"
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_place_invalid_path(pool: PgPool) {
+        let mut app = router(pool).await;
+        let cases = [
+            ("/place/pizza/1", "team must be 'cookie' or 'milk'"),
+            ("/place/cookie/5", "column must be between 1 and 4"),
+            ("/place/milk/zero", "column must be between 1 and 4"),
+        ];
+
+        for (uri, message) in cases {
+            let response = app
+                .call(
+                    Request::builder()
+                        .method("POST")
+                        .uri(uri)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            assert_eq!(body_text(response).await, message);
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_legal_moves(pool: PgPool) {
+        let state = SharedState::default();
+        let Json(columns) = legal_moves(State(state.clone())).await;
+        assert_eq!(columns, vec![1, 2, 3, 4]);
+
+        for team in [GamePiece::Cookie, GamePiece::Milk].repeat(2) {
+            let next_move = Move { team, column: 2 };
+            place(
+                next_move,
+                Query(PlaceQuery::default()),
+                State(state.clone()),
+                State(pool.clone()),
+            )
+            .await;
+        }
+        let Json(columns) = legal_moves(State(state)).await;
+        assert_eq!(columns, vec![1, 3, 4]);
+    }
+
+    #[sqlx::test]
+    async fn test_history(pool: PgPool) {
+        let state = SharedState::default();
+        let Json(moves) = history(State(state.clone())).await;
+        assert!(moves.is_empty());
+
+        for (team, column) in [
+            (GamePiece::Cookie, 2),
+            (GamePiece::Milk, 4),
+            (GamePiece::Cookie, 1),
+        ] {
+            place(
+                Move { team, column },
+                Query(PlaceQuery::default()),
+                State(state.clone()),
+                State(pool.clone()),
+            )
+            .await;
+        }
+        let Json(moves) = history(State(state)).await;
+        assert_eq!(
+            serde_json::to_value(moves).unwrap(),
+            serde_json::json!([
+                { "team": "cookie", "column": 2 },
+                { "team": "milk", "column": 4 },
+                { "team": "cookie", "column": 1 },
+            ])
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_win_line(pool: PgPool) {
+        let state = SharedState::default();
+        assert_eq!(
+            win_line(State(state.clone())).await.unwrap_err(),
+            StatusCode::NOT_FOUND
+        );
+
+        // Milk builds a staircase so cookie can climb the anti-diagonal
+        let moves = [(2, 1), (3, 2), (4, 3)]
+            .into_iter()
+            .flat_map(|(column, height)| (0..height).map(move |_| (GamePiece::Milk, column)))
+            .chain((1..=4).map(|column| (GamePiece::Cookie, column)));
+        for (team, column) in moves {
+            place(
+                Move { team, column },
+                Query(PlaceQuery::default()),
+                State(state.clone()),
+                State(pool.clone()),
+            )
+            .await;
+        }
+
+        let Json(cells) = win_line(State(state.clone())).await.unwrap();
+        assert_eq!(
+            serde_json::to_value(cells).unwrap(),
+            serde_json::json!([
+                { "row": 0, "col": 3 },
+                { "row": 1, "col": 2 },
+                { "row": 2, "col": 1 },
+                { "row": 3, "col": 0 },
+            ])
+        );
+
+        // A random board replaces the line with its own, if it has one
+        randomize(
+            Query(RandomQuery { seed: Some(7) }),
+            State(state.clone()),
+            State(pool),
+        )
+        .await
+        .unwrap();
+        let mut expected = GameBoard::default();
+        expected.reseed(7);
+        expected.randomize();
+        let cells = win_line(State(state)).await.map(|Json(cells)| {
+            cells
+                .iter()
+                .map(|cell| (cell.row, cell.col))
+                .collect::<Vec<_>>()
+        });
+        match cells {
+            Ok(cells) => assert_eq!(cells, expected.win_line()),
+            Err(status) => {
+                assert_eq!(status, StatusCode::NOT_FOUND);
+                assert!(expected.win_line().is_empty());
+            }
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_status(pool: PgPool) {
+        let state = SharedState::default();
+        let Json(actual) = status(State(state.clone())).await;
+        assert_eq!(
+            serde_json::to_value(actual).unwrap(),
+            serde_json::json!({ "state": "running", "winner": null })
+        );
+
+        for _ in 0..4 {
+            let next_move = Move {
+                team: GamePiece::Cookie,
+                column: 1,
+            };
+            place(
+                next_move,
+                Query(PlaceQuery::default()),
+                State(state.clone()),
+                State(pool.clone()),
+            )
+            .await;
+        }
+        let Json(actual) = status(State(state)).await;
+        assert_eq!(
+            serde_json::to_value(actual).unwrap(),
+            serde_json::json!({ "state": "winner", "winner": "cookie" })
+        );
+
+        assert_eq!(
+            GameStatus::from(GameState::Draw),
+            GameStatus {
+                state: "draw",
+                winner: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_suggest() {
+        let state = SharedState::default();
+        let Json(column) = suggest(Path("milk".to_string()), State(state.clone()))
+            .await
+            .unwrap();
+        assert_eq!(column, 1);
+
+        for _ in 0..4 {
+            state.lock().await.place(GamePiece::Cookie, 3).unwrap();
+        }
+        let (status, _) = suggest(Path("milk".to_string()), State(state.clone()))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (status, _) = suggest(Path("pizza".to_string()), State(state))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    async fn test_turn_enforcement(pool: PgPool) {
+        let mut app = router(pool.clone()).await;
+        let post = |uri: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap()
+        };
+        app.call(post("/reset")).await.unwrap();
+        async fn next_turn(app: &mut axum::Router) -> String {
+            let response = app
+                .call(Request::builder().uri("/turn").body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            body_text(response).await
+        }
+        assert_eq!(next_turn(&mut app).await, r#""cookie""#);
+
+        let response = app.call(post("/place/milk/1?enforce=true")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let response = app
+            .call(post("/place/cookie/1?enforce=true"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(next_turn(&mut app).await, r#""milk""#);
+        let response = app
+            .call(post("/place/cookie/1?enforce=true"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let response = app.call(post("/place/milk/1?enforce=true")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Without enforcement either team can move at any time
+        for _ in 0..2 {
+            let response = app.call(post("/place/milk/2")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        assert_eq!(next_turn(&mut app).await, r#""cookie""#);
+
+        // The turn is saved with the board
+        app.call(post("/place/cookie/3")).await.unwrap();
+        let mut reloaded = router(pool).await;
+        assert_eq!(next_turn(&mut reloaded).await, r#""milk""#);
+        let response = reloaded
+            .call(post("/place/cookie/3?enforce=true"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[sqlx::test]
+    async fn test_place_json(pool: PgPool) {
+        let mut app = router(pool).await;
+        let post = |uri: &str, body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("Content-Type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+        app.call(post("/reset", "")).await.unwrap();
+
+        let response = app
+            .call(post("/move", r#"{"team": "cookie", "column": 2}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let from_json = body_text(response).await;
+        app.call(post("/undo", "")).await.unwrap();
+        let response = app.call(post("/place/cookie/2", "")).await.unwrap();
+        assert_eq!(body_text(response).await, from_json);
+
+        for body in [
+            r#"{"team": "cookie", "column": 5}"#,
+            r#"{"team": "cookie", "column": -1}"#,
+            r#"{"team": "sugar", "column": 1}"#,
+            r#"{"team": "cookie"}"#,
+        ] {
+            let response = app.call(post("/move", body)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+
+        for _ in 0..3 {
+            app.call(post("/move", r#"{"team": "milk", "column": 2}"#))
+                .await
+                .unwrap();
+        }
+        let response = app
+            .call(post("/move", r#"{"team": "milk", "column": 2}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[sqlx::test]
+    async fn test_place_error_codes(pool: PgPool) {
+        let state = SharedState::default();
+        let play = |team, column| {
+            place(
+                Move { team, column },
+                Query(PlaceQuery::default()),
+                State(state.clone()),
+                State(pool.clone()),
+            )
+        };
+
+        for team in [GamePiece::Milk, GamePiece::Cookie].repeat(2) {
+            assert_eq!(play(team, 1).await.status(), StatusCode::OK);
+        }
+        let response = play(GamePiece::Cookie, 1).await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        for _ in 0..4 {
+            play(GamePiece::Cookie, 2).await;
+        }
+        let response = play(GamePiece::Milk, 3).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[sqlx::test]
+    async fn test_stats(pool: PgPool) {
+        let mut app = router(pool).await;
+        let post = |uri: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap()
+        };
+        async fn stats(app: &mut axum::Router) -> serde_json::Value {
+            let request = Request::builder()
+                .uri("/stats")
+                .body(Body::empty())
+                .unwrap();
+            let body = body_text(app.call(request).await.unwrap()).await;
+            serde_json::from_str(&body).unwrap()
+        }
+        app.call(post("/reset-stats")).await.unwrap();
+
+        for _ in 0..4 {
+            app.call(post("/place/cookie/2")).await.unwrap();
+        }
+        let won = serde_json::json!({ "cookie_wins": 1, "milk_wins": 0, "draws": 0 });
+        assert_eq!(stats(&mut app).await, won);
+
+        app.call(post("/reset")).await.unwrap();
+        assert_eq!(stats(&mut app).await, won);
+
+        let response = app.call(post("/reset-stats")).await.unwrap();
+        let cleared = serde_json::json!({ "cookie_wins": 0, "milk_wins": 0, "draws": 0 });
+        let body: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(body, cleared);
+        assert_eq!(stats(&mut app).await, cleared);
+
+        // Undoing a win that was cleared from the stats must not underflow them
+        for _ in 0..4 {
+            app.call(post("/place/milk/1")).await.unwrap();
+        }
+        app.call(post("/reset-stats")).await.unwrap();
+        let response = app.call(post("/undo")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(stats(&mut app).await, cleared);
+    }
+
+    #[sqlx::test]
+    async fn test_board_ascii(pool: PgPool) {
+        let mut app = router(pool).await;
+        let request = |method: &str, uri: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap()
+        };
+        app.call(request("POST", "/reset")).await.unwrap();
+        app.call(request("POST", "/place/cookie/1")).await.unwrap();
+
+        let response = app
+            .call(request("GET", "/board?style=ascii"))
+            .await
+            .unwrap();
+        assert_eq!(
+            body_text(response).await,
+            "|....|\n\
+             |....|\n\
+             |....|\n\
+             |C...|\n\
+             +----+\n"
+        );
+
+        let response = app.call(request("GET", "/board")).await.unwrap();
+        assert!(body_text(response).await.starts_with("⬜⬛⬛⬛⬛⬜\n"));
+        let response = app
+            .call(request("GET", "/board?style=sepia"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    async fn test_undo(pool: PgPool) {
+        let mut app = router(pool.clone()).await;
+        let post = |uri: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.call(post("/undo")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app.call(post("/reset")).await.unwrap();
+        let empty = body_text(response).await;
+        app.call(post("/place/milk/4")).await.unwrap();
+        let response = app.call(post("/undo")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_text(response).await, empty);
+
+        // Moves made before a restart can still be taken back
+        app.call(post("/place/milk/4")).await.unwrap();
+        let mut reloaded = router(pool.clone()).await;
+        let response = reloaded.call(post("/undo")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_text(response).await, empty);
+
+        // A random board has no moves to take back
+        let random = Request::builder()
+            .uri("/random-board")
+            .body(Body::empty())
+            .unwrap();
+        reloaded.call(random).await.unwrap();
+        let response = reloaded.call(post("/undo")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    async fn test_board_survives_reload(pool: PgPool) {
+        let mut app = router(pool.clone()).await;
+        let response = app
+            .call(
+                Request::builder()
+                    .method("POST")
+                    .uri("/place/cookie/2")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let placed = body_text(response).await;
+
+        let mut reloaded = router(pool).await;
+        let response = reloaded
+            .call(
+                Request::builder()
+                    .uri("/board")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(body_text(response).await, placed);
+        assert!(placed.contains("⬜⬛🍪⬛⬛⬜\n⬜⬜⬜⬜⬜⬜"));
+    }
 }
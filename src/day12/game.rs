@@ -1,11 +1,12 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
 use rand::{rngs::StdRng, Rng, SeedableRng};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug)]
 pub enum GameError {
     ColumnFull,
+    InvalidColumn,
     GameOver,
 }
 
@@ -16,6 +17,42 @@ pub enum GamePiece {
     Milk,
 }
 
+impl GamePiece {
+    pub fn opponent(&self) -> GamePiece {
+        match self {
+            GamePiece::Cookie => GamePiece::Milk,
+            GamePiece::Milk => GamePiece::Cookie,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GamePiece::Cookie => "cookie",
+            GamePiece::Milk => "milk",
+        }
+    }
+
+    fn symbol(&self, style: BoardStyle) -> String {
+        match (style, self) {
+            (BoardStyle::Emoji, _) => self.to_string(),
+            (BoardStyle::Ascii, GamePiece::Cookie) => "C".to_string(),
+            (BoardStyle::Ascii, GamePiece::Milk) => "M".to_string(),
+        }
+    }
+}
+
+impl FromStr for GamePiece {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cookie" => Ok(GamePiece::Cookie),
+            "milk" => Ok(GamePiece::Milk),
+            _ => Err(()),
+        }
+    }
+}
+
 impl Display for GamePiece {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -25,6 +62,14 @@ impl Display for GamePiece {
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BoardStyle {
+    #[default]
+    Emoji,
+    Ascii,
+}
+
 #[derive(Clone, Copy, Default)]
 pub enum GameState {
     #[default]
@@ -33,65 +78,302 @@ pub enum GameState {
     Draw,
 }
 
+// Finished games since startup, kept across board resets
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct GameStats {
+    pub cookie_wins: u64,
+    pub milk_wins: u64,
+    pub draws: u64,
+}
+
+impl GameStats {
+    fn counter(&mut self, state: GameState) -> Option<&mut u64> {
+        match state {
+            GameState::Running => None,
+            GameState::Winner(GamePiece::Cookie) => Some(&mut self.cookie_wins),
+            GameState::Winner(GamePiece::Milk) => Some(&mut self.milk_wins),
+            GameState::Draw => Some(&mut self.draws),
+        }
+    }
+}
+
+pub struct Placement {
+    // 1-based, counted from the bottom of the board
+    pub row: usize,
+    pub state: GameState,
+}
+
+#[derive(Clone)]
 pub struct GameBoard {
     rng: StdRng,
-    board: [[Option<GamePiece>; 4]; 4],
+    size: usize,
+    // How many pieces in a row win, a full line unless configured otherwise
+    win_length: usize,
+    board: Vec<Vec<Option<GamePiece>>>,
+    // Moves in the order they were played, used to take them back
+    history: Vec<(GamePiece, usize)>,
+    // Whoever placed the most recent piece still on the board, decides whose turn it is
+    last_player: Option<GamePiece>,
+    // (row, column) of the cells that decided the game, empty unless there is a winner
+    win_line: Vec<(usize, usize)>,
+    stats: GameStats,
     pub state: GameState,
 }
 
 impl Default for GameBoard {
     fn default() -> Self {
+        Self::new(4)
+    }
+}
+
+impl Display for GameBoard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render(BoardStyle::Emoji))
+    }
+}
+
+impl GameBoard {
+    pub fn new(size: usize) -> Self {
         Self {
             rng: StdRng::seed_from_u64(2024),
-            board: Default::default(),
+            size,
+            win_length: size,
+            board: vec![vec![None; size]; size],
+            history: Vec::new(),
+            last_player: None,
+            win_line: Vec::new(),
+            stats: GameStats::default(),
             state: Default::default(),
         }
     }
-}
 
-impl Display for GameBoard {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    pub fn render(&self, style: BoardStyle) -> String {
+        let (wall, corner, floor, empty) = match style {
+            BoardStyle::Emoji => ("⬜", "⬜", "⬜", "⬛"),
+            BoardStyle::Ascii => ("|", "+", "-", "."),
+        };
+        let mut out = String::new();
         for row in self.board.iter() {
-            // Print left wall
-            write!(f, "⬜")?;
+            out.push_str(wall);
             for cell in row.iter() {
-                if let Some(piece) = cell {
-                    write!(f, "{piece}")?;
-                } else {
-                    write!(f, "⬛")?;
+                match cell {
+                    Some(piece) => out.push_str(&piece.symbol(style)),
+                    None => out.push_str(empty),
                 }
             }
-            // Print right wall, note that this is writeln! not write!
-            writeln!(f, "⬜")?;
+            out.push_str(wall);
+            out.push('\n');
         }
-        // Print bottom wall
-        writeln!(f, "⬜⬜⬜⬜⬜⬜")?;
-        // Print game state if necessary
+        // Bottom wall, including both corners
+        out.push_str(&format!("{corner}{}{corner}\n", floor.repeat(self.size)));
         if let GameState::Winner(winner) = self.state {
-            writeln!(f, "{winner} wins!")?;
+            out.push_str(&format!("{} wins!\n", winner.symbol(style)));
         } else if let GameState::Draw = self.state {
-            writeln!(f, "No winner.")?;
+            out.push_str("No winner.\n");
         }
-        Ok(())
+        out
     }
-}
 
-impl GameBoard {
-    pub fn place(&mut self, team: GamePiece, column: usize) -> Result<GameState, GameError> {
+    // A fresh default board that keeps the stats of the games played so far
+    pub fn reset(&mut self) {
+        *self = GameBoard {
+            stats: self.stats,
+            ..GameBoard::default()
+        };
+    }
+
+    pub fn stats(&self) -> GameStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = GameStats::default();
+    }
+
+    pub fn with_win_length(mut self, win_length: usize) -> Self {
+        self.win_length = win_length.clamp(1, self.size);
+        self
+    }
+
+    // A format byte and the board size, one byte per cell row by row, one byte each for
+    // the game state and the last player, then a (team, column) pair for every move in
+    // the history
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let size = u8::try_from(self.size).expect("board sizes should fit in a byte");
+        let cells = self.board.iter().flatten().map(|&cell| piece_to_byte(cell));
+        let moves = self
+            .history
+            .iter()
+            .flat_map(|&(team, column)| [piece_to_byte(Some(team)), column as u8]);
+        [BYTES_FORMAT, size]
+            .into_iter()
+            .chain(cells)
+            .chain([state_to_byte(self.state), piece_to_byte(self.last_player)])
+            .chain(moves)
+            .collect()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let Some((&BYTES_FORMAT, bytes)) = bytes.split_first() else {
+            return Self::from_legacy_bytes(bytes);
+        };
+        let (&size, bytes) = bytes.split_first()?;
+        let size = usize::from(size);
+        if size == 0 || bytes.len() <= size * size {
+            return None;
+        }
+        let (cells, bytes) = bytes.split_at(size * size);
+        let [state, last_player, moves @ ..] = bytes else {
+            return None;
+        };
+
+        let mut game = GameBoard::from_cells(size, cells, *state)?;
+        game.last_player = byte_to_piece(*last_player)?;
+        if moves.len() % 2 != 0 {
+            return None;
+        }
+        for pair in moves.chunks_exact(2) {
+            let team = byte_to_piece(pair[0])??;
+            let column = usize::from(pair[1]);
+            if column >= size {
+                return None;
+            }
+            game.history.push((team, column));
+        }
+        Some(game)
+    }
+
+    // Boards saved before the history was persisted are just the cells followed by the state
+    fn from_legacy_bytes(bytes: &[u8]) -> Option<Self> {
+        let (&state, cells) = bytes.split_last()?;
+        let size = cells.len().isqrt();
+        if size == 0 || size * size != cells.len() {
+            return None;
+        }
+        GameBoard::from_cells(size, cells, state)
+    }
+
+    fn from_cells(size: usize, cells: &[u8], state: u8) -> Option<Self> {
+        let mut game = GameBoard::new(size);
+        for (cell, &byte) in game.board.iter_mut().flatten().zip(cells) {
+            *cell = byte_to_piece(byte)?;
+        }
+        game.state = byte_to_state(state)?;
+        if let GameState::Winner(_) = game.state {
+            game.win_line = game.find_win().map(|(_, line)| line).unwrap_or_default();
+        }
+        Some(game)
+    }
+
+    pub fn place(&mut self, team: GamePiece, column: usize) -> Result<Placement, GameError> {
+        if column >= self.size {
+            return Err(GameError::InvalidColumn);
+        }
         if let GameState::Running = self.state {
-            let available_index = (0..4)
+            if !self.legal_columns().contains(&(column + 1)) {
+                return Err(GameError::ColumnFull);
+            }
+            let available_index = (0..self.size)
                 .rev()
                 .find(|&row| self.board[row][column].is_none())
-                .ok_or(GameError::ColumnFull)?;
+                .expect("legal column should have an empty cell");
 
             self.board[available_index][column] = Some(team);
-            Ok(self.update_state())
+            self.history.push((team, column));
+            self.last_player = Some(team);
+            Ok(Placement {
+                row: self.size - available_index,
+                state: self.update_state(),
+            })
         } else {
             Err(GameError::GameOver)
         }
     }
 
+    // 1-based columns that can still take a piece, empty once the game is over
+    pub fn legal_columns(&self) -> Vec<usize> {
+        if !matches!(self.state, GameState::Running) {
+            return Vec::new();
+        }
+        (0..self.size)
+            .filter(|&column| self.board[0][column].is_none())
+            .map(|column| column + 1)
+            .collect()
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    // One-ply lookahead: win if possible, otherwise block, otherwise play leftmost.
+    // Returns a 1-based column, or None once the game is over.
+    pub fn suggest(&self, team: GamePiece) -> Option<usize> {
+        let columns = self.legal_columns();
+        let wins = |team: GamePiece, column: usize| {
+            let mut trial = self.clone();
+            matches!(
+                trial.place(team, column - 1),
+                Ok(Placement {
+                    state: GameState::Winner(winner),
+                    ..
+                }) if winner == team
+            )
+        };
+        columns
+            .iter()
+            .find(|&&column| wins(team, column))
+            .or_else(|| {
+                columns
+                    .iter()
+                    .find(|&&column| wins(team.opponent(), column))
+            })
+            .or_else(|| columns.first())
+            .copied()
+    }
+
+    pub fn history(&self) -> &[(GamePiece, usize)] {
+        &self.history
+    }
+
+    // Rows are counted from the top, the same order the board is displayed in
+    pub fn win_line(&self) -> &[(usize, usize)] {
+        &self.win_line
+    }
+
+    pub fn last_player(&self) -> Option<GamePiece> {
+        self.last_player
+    }
+
+    // Cookie opens, then teams alternate
+    pub fn next_turn(&self) -> GamePiece {
+        self.last_player()
+            .map_or(GamePiece::Cookie, |team| team.opponent())
+    }
+
+    // Takes back the last move, returning None when there is nothing to undo
+    pub fn undo(&mut self) -> Option<(GamePiece, usize)> {
+        let (team, column) = self.history.pop()?;
+        self.last_player = self.history.last().map(|&(team, _)| team);
+        let top = (0..self.size)
+            .find(|&row| self.board[row][column].is_some())
+            .expect("a played column should have a piece");
+        self.board[top][column] = None;
+
+        // The game was running before this move, so the state can only have been decided by it.
+        // The win may predate a stats reset or a reload, in which case it was never counted
+        if let Some(counter) = self.stats.counter(self.state) {
+            *counter = counter.saturating_sub(1);
+        }
+        self.state = GameState::Running;
+        self.win_line.clear();
+        self.update_state();
+        Some((team, column))
+    }
+
+    // A random board has no move order, so there is nothing to undo until the next reset
     pub fn randomize(&mut self) {
+        self.history.clear();
+        self.last_player = None;
         for row in self.board.iter_mut() {
             for cell in row.iter_mut() {
                 if self.rng.gen::<bool>() {
@@ -101,49 +383,124 @@ impl GameBoard {
                 }
             }
         }
+        // The outcome belongs to the new board, but it wasn't played so it isn't counted
+        let stats = self.stats;
+        self.state = GameState::Running;
+        self.win_line.clear();
+        self.update_state();
+        self.stats = stats;
     }
 
     fn update_state(&mut self) -> GameState {
         self.state = if let GameState::Running = self.state {
-            if let Some(winner) = self.get_combinations().find_map(all_same) {
+            if let Some((winner, line)) = self.find_win() {
+                self.win_line = line;
                 GameState::Winner(winner)
             } else if self
                 .get_combinations()
-                .all(|combination| combination.iter().all(Option::is_some))
+                .all(|combination| combination.iter().all(|&cell| self.cell(cell).is_some()))
             {
                 GameState::Draw
             } else {
                 GameState::Running
             }
         } else {
-            self.state
+            return self.state;
         };
+        if let Some(counter) = self.stats.counter(self.state) {
+            *counter += 1;
+        }
         self.state
     }
 
-    fn get_combinations(&self) -> impl Iterator<Item = Vec<Option<GamePiece>>> + '_ {
-        (0..4)
+    fn find_win(&self) -> Option<(GamePiece, Vec<(usize, usize)>)> {
+        self.get_combinations().find_map(|combination| {
+            let winner = all_same(combination.iter().map(|&cell| self.cell(cell)))?;
+            Some((winner, combination))
+        })
+    }
+
+    fn cell(&self, (row, column): (usize, usize)) -> Option<GamePiece> {
+        self.board[row][column]
+    }
+
+    // Every window of win_length cells along a row, column or diagonal
+    fn get_combinations(&self) -> impl Iterator<Item = Vec<(usize, usize)>> + '_ {
+        (0..self.size)
             .map(|row| self.get_row(row))
-            .chain((0..4).map(|column| self.get_column(column)))
+            .chain((0..self.size).map(|column| self.get_column(column)))
             .chain(self.get_diagonals())
+            .flat_map(|line| {
+                line.windows(self.win_length)
+                    .map(<[_]>::to_vec)
+                    .collect::<Vec<_>>()
+            })
+    }
+
+    fn get_row(&self, row: usize) -> Vec<(usize, usize)> {
+        (0..self.size).map(|column| (row, column)).collect()
+    }
+
+    fn get_column(&self, column: usize) -> Vec<(usize, usize)> {
+        (0..self.size).map(|row| (row, column)).collect()
+    }
+
+    // Both directions, skipping diagonals too short to hold a winning line
+    fn get_diagonals(&self) -> Vec<Vec<(usize, usize)>> {
+        let last = self.size - 1;
+        let reach = (self.size - self.win_length) as isize;
+        let diagonal = |offset: isize, flipped: bool| {
+            (0..self.size)
+                .filter_map(|row| {
+                    let column = row.checked_add_signed(offset).filter(|&c| c <= last)?;
+                    let column = if flipped { last - column } else { column };
+                    Some((row, column))
+                })
+                .collect()
+        };
+        [false, true]
+            .into_iter()
+            .flat_map(|flipped| (-reach..=reach).map(move |offset| diagonal(offset, flipped)))
+            .collect()
+    }
+}
+
+// Legacy boards start with a cell byte, which is never above 2
+const BYTES_FORMAT: u8 = 0x80;
+
+fn piece_to_byte(piece: Option<GamePiece>) -> u8 {
+    match piece {
+        None => 0,
+        Some(GamePiece::Cookie) => 1,
+        Some(GamePiece::Milk) => 2,
     }
+}
 
-    fn get_row(&self, row: usize) -> Vec<Option<GamePiece>> {
-        self.board.iter().map(move |y| &y[row]).cloned().collect()
+fn byte_to_piece(byte: u8) -> Option<Option<GamePiece>> {
+    match byte {
+        0 => Some(None),
+        1 => Some(Some(GamePiece::Cookie)),
+        2 => Some(Some(GamePiece::Milk)),
+        _ => None,
     }
+}
 
-    fn get_column(&self, column: usize) -> Vec<Option<GamePiece>> {
-        self.board[column].to_vec()
+fn state_to_byte(state: GameState) -> u8 {
+    match state {
+        GameState::Running => 0,
+        GameState::Winner(GamePiece::Cookie) => 1,
+        GameState::Winner(GamePiece::Milk) => 2,
+        GameState::Draw => 3,
     }
+}
 
-    fn get_diagonals(&self) -> [Vec<Option<GamePiece>>; 2] {
-        [
-            (0..4).map(move |i| &self.board[i][i]).cloned().collect(),
-            (0..4)
-                .map(move |i| &self.board[i][3 - i])
-                .cloned()
-                .collect(),
-        ]
+fn byte_to_state(byte: u8) -> Option<GameState> {
+    match byte {
+        0 => Some(GameState::Running),
+        1 => Some(GameState::Winner(GamePiece::Cookie)),
+        2 => Some(GameState::Winner(GamePiece::Milk)),
+        3 => Some(GameState::Draw),
+        _ => None,
     }
 }
 
@@ -180,6 +537,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_render_ascii() {
+        let mut game = GameBoard::default();
+        game.place(GamePiece::Cookie, 0).unwrap();
+        assert_eq!(
+            game.render(BoardStyle::Ascii),
+            "|....|\n\
+             |....|\n\
+             |....|\n\
+             |C...|\n\
+             +----+\n"
+        );
+        assert_eq!(game.render(BoardStyle::Emoji), game.to_string());
+    }
+
     #[test]
     fn test_place() {
         let mut game = GameBoard::default();
@@ -241,6 +613,291 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bytes_round_trip() {
+        let mut game = GameBoard::default();
+        game.place(GamePiece::Milk, 2).unwrap();
+        game.place(GamePiece::Cookie, 2).unwrap();
+        let mut restored = GameBoard::from_bytes(&game.to_bytes()).unwrap();
+        assert_eq!(restored.to_string(), game.to_string());
+
+        // The history comes back too, so moves played before a restart can be undone
+        assert_eq!(restored.history(), game.history());
+        assert_eq!(restored.last_player(), Some(GamePiece::Cookie));
+        assert_eq!(restored.undo(), Some((GamePiece::Cookie, 2)));
+        assert_eq!(restored.last_player(), Some(GamePiece::Milk));
+        assert_eq!(restored.undo(), Some((GamePiece::Milk, 2)));
+        assert_eq!(restored.to_string(), GameBoard::default().to_string());
+
+        assert!(GameBoard::from_bytes(&[]).is_none());
+        assert!(GameBoard::from_bytes(&[3; 17]).is_none());
+        // A move without a column
+        let mut truncated = game.to_bytes();
+        truncated.pop();
+        assert!(GameBoard::from_bytes(&truncated).is_none());
+    }
+
+    #[test]
+    fn test_legacy_bytes() {
+        let mut bytes = vec![0; 16];
+        bytes[12] = 1;
+        bytes.push(0);
+        let game = GameBoard::from_bytes(&bytes).unwrap();
+        assert_eq!(game.board[3][0], Some(GamePiece::Cookie));
+        assert!(game.history().is_empty());
+    }
+
+    #[test]
+    fn test_undo_after_randomize() {
+        let mut game = GameBoard::default();
+        game.place(GamePiece::Cookie, 0).unwrap();
+        game.randomize();
+        let randomized = game.to_string();
+        assert!(game.undo().is_none());
+        assert_eq!(game.to_string(), randomized);
+    }
+
+    #[test]
+    fn test_5x5_diagonal_win() {
+        let mut game = GameBoard::new(5);
+        // Build a staircase so cookie lands on the anti-diagonal from bottom-left to top-right
+        for column in 1..5 {
+            for _ in 0..column {
+                game.place(GamePiece::Milk, column).unwrap();
+            }
+        }
+        for column in 0..4 {
+            game.place(GamePiece::Cookie, column).unwrap();
+        }
+        let placement = game.place(GamePiece::Cookie, 4).unwrap();
+        assert!(matches!(
+            placement.state,
+            GameState::Winner(GamePiece::Cookie)
+        ));
+        assert_eq!(placement.row, 5);
+        assert_eq!(
+            game.to_string(),
+            "⬜⬛⬛⬛⬛🍪⬜\n\
+             ⬜⬛⬛⬛🍪🥛⬜\n\
+             ⬜⬛⬛🍪🥛🥛⬜\n\
+             ⬜⬛🍪🥛🥛🥛⬜\n\
+             ⬜🍪🥛🥛🥛🥛⬜\n\
+             ⬜⬜⬜⬜⬜⬜⬜\n\
+             🍪 wins!\n"
+        );
+    }
+
+    #[test]
+    fn test_win_line() {
+        let mut game = GameBoard::default();
+        for column in 1..4 {
+            for _ in 0..column {
+                game.place(GamePiece::Milk, column).unwrap();
+            }
+        }
+        for column in 0..3 {
+            game.place(GamePiece::Cookie, column).unwrap();
+            assert!(game.win_line().is_empty());
+        }
+        game.place(GamePiece::Cookie, 3).unwrap();
+        assert_eq!(game.win_line(), [(0, 3), (1, 2), (2, 1), (3, 0)]);
+
+        let restored = GameBoard::from_bytes(&game.to_bytes()).unwrap();
+        assert_eq!(restored.win_line(), game.win_line());
+
+        game.undo().unwrap();
+        assert!(game.win_line().is_empty());
+    }
+
+    #[test]
+    fn test_randomize_after_win() {
+        let mut game = GameBoard::default();
+        for _ in 0..4 {
+            game.place(GamePiece::Milk, 0).unwrap();
+        }
+        let stats = game.stats();
+
+        for seed in 0..8 {
+            game.reseed(seed);
+            game.randomize();
+            let expected = game.find_win();
+            assert_eq!(
+                game.win_line(),
+                expected.as_ref().map_or(&[][..], |(_, line)| line)
+            );
+            match (game.state, expected) {
+                (GameState::Winner(winner), Some((expected, _))) => assert_eq!(winner, expected),
+                (GameState::Draw, None) => {}
+                _ => panic!("a full board is either won or drawn"),
+            }
+            assert_eq!(game.stats(), stats);
+        }
+    }
+
+    #[test]
+    fn test_5x5_draw() {
+        use GamePiece::{Cookie, Milk};
+
+        let mut game = GameBoard::new(5);
+        let rows = [
+            [Cookie, Milk, Cookie, Milk, Cookie],
+            [Cookie, Milk, Cookie, Milk, Cookie],
+            [Milk, Cookie, Milk, Cookie, Milk],
+            [Cookie, Milk, Cookie, Milk, Cookie],
+            [Cookie, Milk, Cookie, Milk, Cookie],
+        ];
+        let mut last = GameState::Running;
+        for row in rows {
+            for (column, team) in row.into_iter().enumerate() {
+                last = game.place(team, column).unwrap().state;
+            }
+        }
+        assert!(matches!(last, GameState::Draw));
+        assert!(game.to_string().ends_with("⬜⬜⬜⬜⬜⬜⬜\nNo winner.\n"));
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut game = GameBoard::default();
+        assert!(game.undo().is_none());
+
+        game.place(GamePiece::Cookie, 1).unwrap();
+        assert_eq!(game.undo(), Some((GamePiece::Cookie, 1)));
+        assert_eq!(game.to_string(), GameBoard::default().to_string());
+        assert!(game.undo().is_none());
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut game = GameBoard::default();
+        for _ in 0..4 {
+            game.place(GamePiece::Milk, 0).unwrap();
+        }
+        let expected = GameStats {
+            milk_wins: 1,
+            ..Default::default()
+        };
+        assert_eq!(game.stats(), expected);
+        // Placing after the game is over doesn't count it twice
+        assert!(game.place(GamePiece::Cookie, 1).is_err());
+        assert_eq!(game.stats(), expected);
+
+        // Taking back the winning move takes back the win
+        game.undo().unwrap();
+        assert_eq!(game.stats(), GameStats::default());
+        game.place(GamePiece::Milk, 0).unwrap();
+
+        game.reset();
+        assert_eq!(game.stats(), expected);
+        assert!(game.history().is_empty());
+        game.reset_stats();
+        assert_eq!(game.stats(), GameStats::default());
+    }
+
+    #[test]
+    fn test_undo_win_after_reset_stats() {
+        let mut game = GameBoard::default();
+        for _ in 0..4 {
+            game.place(GamePiece::Milk, 0).unwrap();
+        }
+        game.reset_stats();
+        game.undo().unwrap();
+        assert_eq!(game.stats(), GameStats::default());
+        assert!(matches!(game.state, GameState::Running));
+    }
+
+    #[test]
+    fn test_undo_win() {
+        let mut game = GameBoard::default();
+        for _ in 0..3 {
+            game.place(GamePiece::Milk, 0).unwrap();
+        }
+        let placement = game.place(GamePiece::Milk, 0).unwrap();
+        assert!(matches!(
+            placement.state,
+            GameState::Winner(GamePiece::Milk)
+        ));
+
+        game.undo().unwrap();
+        assert!(matches!(game.state, GameState::Running));
+        assert!(game.board[0][0].is_none());
+        assert_eq!(game.board[1][0], Some(GamePiece::Milk));
+        game.place(GamePiece::Cookie, 0).unwrap();
+    }
+
+    #[test]
+    fn test_suggest() {
+        let mut game = GameBoard::default();
+        assert_eq!(game.suggest(GamePiece::Cookie), Some(1));
+
+        // Milk threatens column 3, cookie should block it
+        for _ in 0..3 {
+            game.place(GamePiece::Milk, 2).unwrap();
+        }
+        assert_eq!(game.suggest(GamePiece::Cookie), Some(3));
+
+        // Cookie can now win in column 2, which beats blocking
+        for _ in 0..3 {
+            game.place(GamePiece::Cookie, 1).unwrap();
+        }
+        assert_eq!(game.suggest(GamePiece::Cookie), Some(2));
+        assert_eq!(game.suggest(GamePiece::Milk), Some(3));
+
+        game.place(GamePiece::Milk, 2).unwrap();
+        assert_eq!(game.suggest(GamePiece::Cookie), None);
+    }
+
+    #[test]
+    fn test_reseed() {
+        let randomized = |seed: Option<u64>| {
+            let mut game = GameBoard::default();
+            if let Some(seed) = seed {
+                game.reseed(seed);
+            }
+            game.randomize();
+            game.to_string()
+        };
+
+        assert_eq!(randomized(None), randomized(Some(2024)));
+        assert_ne!(randomized(Some(1)), randomized(Some(2)));
+    }
+
+    #[test]
+    fn test_win_length() {
+        let mut game = GameBoard::default().with_win_length(3);
+        game.place(GamePiece::Cookie, 1).unwrap();
+        let placement = game.place(GamePiece::Cookie, 2).unwrap();
+        assert!(matches!(placement.state, GameState::Running));
+        let placement = game.place(GamePiece::Cookie, 3).unwrap();
+        assert!(matches!(
+            placement.state,
+            GameState::Winner(GamePiece::Cookie)
+        ));
+
+        // Off the main diagonal, which is too short to win on by default
+        let mut game = GameBoard::default().with_win_length(3);
+        game.place(GamePiece::Milk, 2).unwrap();
+        game.place(GamePiece::Milk, 3).unwrap();
+        game.place(GamePiece::Milk, 3).unwrap();
+        game.place(GamePiece::Cookie, 1).unwrap();
+        game.place(GamePiece::Cookie, 2).unwrap();
+        let placement = game.place(GamePiece::Cookie, 3).unwrap();
+        assert!(matches!(
+            placement.state,
+            GameState::Winner(GamePiece::Cookie)
+        ));
+    }
+
+    #[test]
+    fn test_win_length_two_in_a_row() {
+        let mut game = GameBoard::default().with_win_length(3);
+        game.place(GamePiece::Cookie, 0).unwrap();
+        game.place(GamePiece::Milk, 1).unwrap();
+        game.place(GamePiece::Cookie, 2).unwrap();
+        let placement = game.place(GamePiece::Cookie, 3).unwrap();
+        assert!(matches!(placement.state, GameState::Running));
+    }
+
     #[test]
     fn test_get_diagonals() {
         let mut board = GameBoard::default();
@@ -248,7 +905,11 @@ mod tests {
             board.board[i][i] = Some(GamePiece::Cookie);
             board.board[i][3 - i] = Some(GamePiece::Milk);
         }
-        let diagonals = board.get_diagonals();
+        let diagonals: Vec<Vec<_>> = board
+            .get_diagonals()
+            .into_iter()
+            .map(|diagonal| diagonal.into_iter().map(|cell| board.cell(cell)).collect())
+            .collect();
         assert_eq!(
             diagonals[0],
             vec![
@@ -0,0 +1,78 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use rand::Rng;
+
+const MAX_SNAPSHOTS: usize = 32;
+const SNAPSHOT_TTL: Duration = Duration::from_secs(60 * 60);
+
+struct Snapshot {
+    board: String,
+    taken_at: Instant,
+}
+
+#[derive(Default)]
+pub struct Snapshots {
+    snapshots: HashMap<String, Snapshot>,
+}
+
+impl Snapshots {
+    pub fn insert(&mut self, board: String) -> String {
+        self.evict();
+        let token = rand::thread_rng()
+            .sample_iter(&rand::distributions::Alphanumeric)
+            .take(16)
+            .map(char::from)
+            .collect::<String>();
+        self.snapshots.insert(
+            token.clone(),
+            Snapshot {
+                board,
+                taken_at: Instant::now(),
+            },
+        );
+        token
+    }
+
+    pub fn get(&self, token: &str) -> Option<&str> {
+        self.snapshots
+            .get(token)
+            .filter(|snapshot| snapshot.taken_at.elapsed() < SNAPSHOT_TTL)
+            .map(|snapshot| snapshot.board.as_str())
+    }
+
+    fn evict(&mut self) {
+        // Drop expired snapshots, then the oldest ones until there is room for another
+        self.snapshots
+            .retain(|_, snapshot| snapshot.taken_at.elapsed() < SNAPSHOT_TTL);
+        while self.snapshots.len() >= MAX_SNAPSHOTS {
+            let Some(oldest) = self
+                .snapshots
+                .iter()
+                .min_by_key(|(_, snapshot)| snapshot.taken_at)
+                .map(|(token, _)| token.clone())
+            else {
+                break;
+            };
+            self.snapshots.remove(&oldest);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicts_oldest() {
+        let mut snapshots = Snapshots::default();
+        let first = snapshots.insert("first".to_string());
+        for i in 0..MAX_SNAPSHOTS {
+            snapshots.insert(i.to_string());
+        }
+        assert_eq!(snapshots.snapshots.len(), MAX_SNAPSHOTS);
+        assert!(snapshots.get(&first).is_none());
+    }
+}
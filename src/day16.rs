@@ -1,64 +1,262 @@
+use std::{str::FromStr, sync::Arc};
+
 use axum::{
-    http::StatusCode,
+    extract::{FromRef, Query, State},
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        HeaderMap, StatusCode,
+    },
     response::{IntoResponse, Response},
     routing::{get, post},
     Json,
 };
-use axum_extra::extract::{cookie::Cookie, CookieJar};
-use jsonwebtoken::{errors::ErrorKind, Algorithm, DecodingKey, EncodingKey, Header};
+use axum_extra::extract::{
+    cookie::{Cookie, SameSite},
+    CookieJar,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+    errors::ErrorKind,
+    jwk::{
+        AlgorithmParameters, CommonParameters, Jwk, JwkSet, KeyAlgorithm, PublicKeyUse,
+        RSAKeyParameters, RSAKeyType,
+    },
+    Algorithm, DecodingKey, EncodingKey, Header,
+};
+use rsa::{pkcs8::DecodePublicKey, traits::PublicKeyParts, RsaPublicKey};
+use serde::Deserialize;
 use serde_json::Value;
+use time::Duration;
 use tracing::error;
 
+use jwks::JwksCache;
+
+mod jwks;
+
 const SANTA_RSA_KEY: &[u8] = include_bytes!("day16_santa_public_key.pem");
+const GIFT_TTL: Duration = Duration::hours(1);
+const GIFT_ALGORITHMS: [Algorithm; 3] = [Algorithm::HS256, Algorithm::HS384, Algorithm::HS512];
+
+#[derive(Clone)]
+struct GiftSecret(Arc<str>);
+
+impl Default for GiftSecret {
+    fn default() -> Self {
+        Self("cch24".into())
+    }
+}
+
+impl GiftSecret {
+    fn from_env() -> Self {
+        std::env::var("JWT_SECRET")
+            .map(|secret| Self(secret.into()))
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Clone)]
+struct AppState {
+    keys: Arc<JwkSet>,
+    issuer: Arc<JwksCache>,
+    secret: GiftSecret,
+}
+
+impl FromRef<AppState> for GiftSecret {
+    fn from_ref(state: &AppState) -> Self {
+        state.secret.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<JwkSet> {
+    fn from_ref(state: &AppState) -> Self {
+        state.keys.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<JwksCache> {
+    fn from_ref(state: &AppState) -> Self {
+        state.issuer.clone()
+    }
+}
 
 pub fn router() -> axum::Router {
+    router_with(GiftSecret::from_env(), JwksCache::from_env())
+}
+
+fn router_with(secret: GiftSecret, issuer: JwksCache) -> axum::Router {
     axum::Router::new()
         .route("/wrap", post(wrap))
         .route("/unwrap", get(unwrap))
         .route("/decode", post(decode))
+        .route("/verify", post(verify))
+        .route("/keys", get(keys))
+        .with_state(AppState {
+            keys: Arc::new(santa_jwks()),
+            issuer: Arc::new(issuer),
+            secret,
+        })
+}
+
+fn santa_jwks() -> JwkSet {
+    let pem = std::str::from_utf8(SANTA_RSA_KEY).expect("santa public key must be utf-8");
+    let key = RsaPublicKey::from_public_key_pem(pem).expect("santa public key must be valid");
+    JwkSet {
+        keys: vec![rsa_jwk("santa", &key)],
+    }
+}
+
+fn rsa_jwk(kid: &str, key: &RsaPublicKey) -> Jwk {
+    Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(KeyAlgorithm::RS256),
+            key_id: Some(kid.to_string()),
+            ..Default::default()
+        },
+        algorithm: AlgorithmParameters::RSA(RSAKeyParameters {
+            key_type: RSAKeyType::RSA,
+            n: URL_SAFE_NO_PAD.encode(key.n().to_bytes_be()),
+            e: URL_SAFE_NO_PAD.encode(key.e().to_bytes_be()),
+        }),
+    }
+}
+
+async fn keys(State(jwks): State<Arc<JwkSet>>) -> Json<JwkSet> {
+    Json(jwks.as_ref().clone())
 }
 
-async fn wrap(jar: CookieJar, Json(body): Json<Value>) -> CookieJar {
+#[derive(Deserialize, Default)]
+struct WrapQuery {
+    alg: Option<String>,
+    ttl_secs: Option<u64>,
+}
+
+async fn wrap(
+    State(secret): State<GiftSecret>,
+    jar: CookieJar,
+    Query(query): Query<WrapQuery>,
+    Json(mut body): Json<Value>,
+) -> Result<CookieJar, (StatusCode, &'static str)> {
+    let algorithm = match query.alg.as_deref() {
+        Some(alg) => Algorithm::from_str(alg)
+            .ok()
+            .filter(|algorithm| GIFT_ALGORITHMS.contains(algorithm))
+            .ok_or((
+                StatusCode::BAD_REQUEST,
+                "alg must be one of HS256, HS384 or HS512",
+            ))?,
+        None => Algorithm::HS256,
+    };
+    if let Some(ttl_secs) = query.ttl_secs {
+        let claims = body.as_object_mut().ok_or((
+            StatusCode::BAD_REQUEST,
+            "ttl_secs requires a JSON object payload",
+        ))?;
+        let exp = jsonwebtoken::get_current_timestamp().saturating_add(ttl_secs);
+        claims.insert("exp".to_string(), exp.into());
+    }
+    // With a TTL the cookie expires along with the gift inside it. Without one the
+    // gift itself never expires, and the cookie just falls back to GIFT_TTL.
+    let max_age = query.ttl_secs.map_or(GIFT_TTL, |ttl_secs| {
+        i64::try_from(ttl_secs).map_or(Duration::MAX, Duration::seconds)
+    });
     let jwt = jsonwebtoken::encode(
-        &Header::default(),
+        &Header::new(algorithm),
         &body,
-        &EncodingKey::from_secret("cch24".as_ref()),
+        &EncodingKey::from_secret(secret.0.as_bytes()),
     )
     .expect("jwt token creation must succeed");
-    jar.add(Cookie::new("gift", jwt))
+    Ok(jar.add(
+        Cookie::build(("gift", jwt))
+            .http_only(true)
+            .secure(true)
+            .same_site(SameSite::Strict)
+            .max_age(max_age),
+    ))
+}
+
+#[derive(Deserialize, Default)]
+struct ClaimsQuery {
+    #[serde(default)]
+    pretty: bool,
 }
 
-async fn unwrap(jar: CookieJar) -> Response {
+fn claims_response(claims: &Value, pretty: bool) -> Response {
+    if !pretty {
+        return claims.to_string().into_response();
+    }
+    let body = serde_json::to_string_pretty(claims).expect("claims must serialize");
+    ([(CONTENT_TYPE, "application/json")], body).into_response()
+}
+
+async fn unwrap(
+    State(secret): State<GiftSecret>,
+    Query(query): Query<ClaimsQuery>,
+    jar: CookieJar,
+) -> Response {
     let Some(gift) = jar.get("gift") else {
         return StatusCode::BAD_REQUEST.into_response();
     };
-    let jwt = gift.value();
+    let Some(claims) = open_gift(&secret, gift.value()) else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    claims_response(&claims, query.pretty)
+}
 
+async fn verify(
+    State(secret): State<GiftSecret>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|jwt| open_gift(&secret, jwt))
+        .map(Json)
+        .ok_or(StatusCode::UNAUTHORIZED)
+}
+
+fn open_gift(secret: &GiftSecret, jwt: &str) -> Option<Value> {
     let mut jwt_validation = jsonwebtoken::Validation::default();
+    jwt_validation.algorithms = GIFT_ALGORITHMS.to_vec();
+    // exp is optional, but enforced without leeway when the gift carries a numeric one.
+    // Any other exp is just another claim, so older gifts that used it freely still open
     jwt_validation.required_spec_claims = Default::default();
-    jwt_validation.validate_exp = false;
-    let Ok(value) = jsonwebtoken::decode::<Value>(
+    jwt_validation.leeway = 0;
+    jsonwebtoken::decode::<Value>(
         jwt,
-        &DecodingKey::from_secret("cch24".as_ref()),
+        &DecodingKey::from_secret(secret.0.as_bytes()),
         &jwt_validation,
-    ) else {
-        return StatusCode::UNAUTHORIZED.into_response();
-    };
-    value.claims.to_string().into_response()
+    )
+    .ok()
+    .map(|data| data.claims)
 }
 
-async fn decode(jwt: String) -> Response {
+async fn decode(
+    State(issuer): State<Arc<JwksCache>>,
+    Query(query): Query<ClaimsQuery>,
+    jwt: String,
+) -> Response {
+    let header = match jsonwebtoken::decode_header(&jwt) {
+        Ok(header) => header,
+        Err(err) => {
+            error!(%err, "jwt header was not valid");
+            return StatusCode::BAD_REQUEST.into_response();
+        }
+    };
+    let key = match issuer.decoding_key(header.kid.as_deref()).await {
+        Some(Ok(key)) => key,
+        Some(Err(status)) => return status.into_response(),
+        None => DecodingKey::from_rsa_pem(SANTA_RSA_KEY).unwrap(),
+    };
+
     let mut jwt_validation = jsonwebtoken::Validation::default();
     jwt_validation.algorithms = vec![Algorithm::RS256, Algorithm::RS512];
     jwt_validation.required_spec_claims = Default::default();
     jwt_validation.validate_exp = false;
-    let decode = jsonwebtoken::decode::<Value>(
-        &jwt,
-        &DecodingKey::from_rsa_pem(SANTA_RSA_KEY).unwrap(),
-        &jwt_validation,
-    );
+    let decode = jsonwebtoken::decode::<Value>(&jwt, &key, &jwt_validation);
     match decode {
-        Ok(value) => value.claims.to_string().into_response(),
+        Ok(value) => claims_response(&value.claims, query.pretty),
         Err(err) if *err.kind() == ErrorKind::InvalidSignature => {
             StatusCode::UNAUTHORIZED.into_response()
         }
@@ -68,3 +266,345 @@ async fn decode(jwt: String) -> Response {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::http::header::SET_COOKIE;
+    use http_body_util::BodyExt;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_wrap_cookie_attributes() {
+        let jar = wrap(
+            State(GiftSecret::default()),
+            CookieJar::new(),
+            Query(WrapQuery::default()),
+            Json(json!({ "gift": "coal" })),
+        )
+        .await
+        .unwrap();
+        let response = jar.into_response();
+        let set_cookie = response.headers()[SET_COOKIE].to_str().unwrap();
+
+        assert!(set_cookie.starts_with("gift="));
+        assert!(set_cookie.contains("HttpOnly"));
+        assert!(set_cookie.contains("Secure"));
+        assert!(set_cookie.contains("SameSite=Strict"));
+        assert!(set_cookie.contains("Max-Age=3600"));
+    }
+
+    #[tokio::test]
+    async fn test_wrap_hs512_round_trip() {
+        let query = WrapQuery {
+            alg: Some("HS512".to_string()),
+            ..Default::default()
+        };
+        let gift = json!({ "gift": "coal", "naughty": true });
+        let jar = wrap(
+            State(GiftSecret::default()),
+            CookieJar::new(),
+            Query(query),
+            Json(gift.clone()),
+        )
+        .await
+        .unwrap();
+        let jwt = jar.get("gift").unwrap().value().to_string();
+        assert_eq!(
+            jsonwebtoken::decode_header(&jwt).unwrap().alg,
+            Algorithm::HS512
+        );
+
+        let response = unwrap(
+            State(GiftSecret::default()),
+            Query(ClaimsQuery::default()),
+            CookieJar::new().add(Cookie::new("gift", jwt)),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let claims: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(claims, gift);
+    }
+
+    #[tokio::test]
+    async fn test_wrap_invalid_alg() {
+        for alg in ["RS256", "none"] {
+            let query = WrapQuery {
+                alg: Some(alg.to_string()),
+                ..Default::default()
+            };
+            let actual = wrap(
+                State(GiftSecret::default()),
+                CookieJar::new(),
+                Query(query),
+                Json(json!({})),
+            )
+            .await;
+            assert_eq!(actual.unwrap_err().0, StatusCode::BAD_REQUEST);
+        }
+    }
+
+    async fn wrap_and_unwrap(query: WrapQuery, gift: Value) -> Response {
+        let jar = wrap(
+            State(GiftSecret::default()),
+            CookieJar::new(),
+            Query(query),
+            Json(gift),
+        )
+        .await
+        .unwrap();
+        let jwt = jar.get("gift").unwrap().value().to_string();
+        unwrap(
+            State(GiftSecret::default()),
+            Query(ClaimsQuery::default()),
+            CookieJar::new().add(Cookie::new("gift", jwt)),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_wrap_ttl() {
+        let query = WrapQuery {
+            ttl_secs: Some(90),
+            ..Default::default()
+        };
+        let jar = wrap(
+            State(GiftSecret::default()),
+            CookieJar::new(),
+            Query(query),
+            Json(json!({ "gift": "coal" })),
+        )
+        .await
+        .unwrap();
+        let cookie = jar.get("gift").unwrap();
+        assert_eq!(cookie.max_age(), Some(Duration::seconds(90)));
+
+        let now = jsonwebtoken::get_current_timestamp();
+        let claims = jsonwebtoken::decode::<Value>(
+            cookie.value(),
+            &DecodingKey::from_secret(GiftSecret::default().0.as_bytes()),
+            &jsonwebtoken::Validation::default(),
+        )
+        .unwrap()
+        .claims;
+        let exp = claims["exp"].as_u64().unwrap();
+        assert!((now + 88..=now + 90).contains(&exp), "exp {exp} now {now}");
+
+        let response = wrap_and_unwrap(
+            WrapQuery {
+                ttl_secs: Some(u64::MAX),
+                ..Default::default()
+            },
+            json!({ "gift": "coal" }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_wrap_expired() {
+        let response =
+            wrap_and_unwrap(WrapQuery::default(), json!({ "gift": "coal", "exp": 1 })).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_wrap_without_ttl() {
+        for gift in [
+            json!({ "gift": "coal" }),
+            json!({ "gift": "coal", "exp": "christmas eve" }),
+        ] {
+            let response = wrap_and_unwrap(WrapQuery::default(), gift.clone()).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(serde_json::from_slice::<Value>(&body).unwrap(), gift);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unwrap_pretty() {
+        let gift = json!({ "gift": "coal", "naughty": true });
+        let jwt = jsonwebtoken::encode(
+            &Header::default(),
+            &gift,
+            &EncodingKey::from_secret(GiftSecret::default().0.as_bytes()),
+        )
+        .unwrap();
+        async fn unwrap_body(jwt: &str, pretty: bool) -> axum::body::Bytes {
+            let response = unwrap(
+                State(GiftSecret::default()),
+                Query(ClaimsQuery { pretty }),
+                CookieJar::new().add(Cookie::new("gift", jwt.to_string())),
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::OK);
+            response.into_body().collect().await.unwrap().to_bytes()
+        }
+
+        let compact = unwrap_body(&jwt, false).await;
+        assert!(!compact.contains(&b'\n'));
+        let pretty = unwrap_body(&jwt, true).await;
+        assert!(pretty.contains(&b'\n'));
+        assert_eq!(
+            serde_json::from_slice::<Value>(&compact).unwrap(),
+            serde_json::from_slice::<Value>(&pretty).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_custom_secret() {
+        let secret = GiftSecret("north pole".into());
+        let gift = json!({ "gift": "coal" });
+        let jar = wrap(
+            State(secret.clone()),
+            CookieJar::new(),
+            Query(WrapQuery::default()),
+            Json(gift.clone()),
+        )
+        .await
+        .unwrap();
+        let jwt = jar.get("gift").unwrap().value().to_string();
+
+        let response = unwrap(
+            State(secret),
+            Query(ClaimsQuery::default()),
+            CookieJar::new().add(Cookie::new("gift", jwt.clone())),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(serde_json::from_slice::<Value>(&body).unwrap(), gift);
+
+        let response = unwrap(
+            State(GiftSecret::default()),
+            Query(ClaimsQuery::default()),
+            CookieJar::new().add(Cookie::new("gift", jwt)),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_verify() {
+        use axum::http::header::CONTENT_TYPE;
+        use tower::ServiceExt;
+
+        let gift = json!({ "gift": "coal", "naughty": true });
+        let jar = wrap(
+            State(GiftSecret::default()),
+            CookieJar::new(),
+            Query(WrapQuery::default()),
+            Json(gift.clone()),
+        )
+        .await
+        .unwrap();
+        let jwt = jar.get("gift").unwrap().value().to_string();
+        let verify = |authorization: String| {
+            axum::extract::Request::builder()
+                .method("POST")
+                .uri("/verify")
+                .header(AUTHORIZATION, authorization)
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+        let app = router_with(GiftSecret::default(), JwksCache::new(None));
+
+        let response = app
+            .clone()
+            .oneshot(verify(format!("Bearer {jwt}")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(serde_json::from_slice::<Value>(&body).unwrap(), gift);
+
+        let response = app.oneshot(verify(format!("Bearer {jwt}x"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_decode_with_jwks() {
+        use rsa::{pkcs1::EncodeRsaPrivateKey, pkcs8::LineEnding, RsaPrivateKey};
+        use tower::ServiceExt;
+        use wiremock::{
+            matchers::{method, path},
+            Mock, MockServer, ResponseTemplate,
+        };
+
+        let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 2048).unwrap();
+        let jwks = JwkSet {
+            keys: vec![rsa_jwk("elf", &private_key.to_public_key())],
+        };
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/jwks.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&jwks))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let app = router_with(
+            GiftSecret::default(),
+            JwksCache::new(Some(format!("{}/jwks.json", server.uri()))),
+        );
+
+        let pem = private_key.to_pkcs1_pem(LineEnding::LF).unwrap();
+        let encoding_key = EncodingKey::from_rsa_pem(pem.as_bytes()).unwrap();
+        let sign = |kid: &str| {
+            let mut header = Header::new(Algorithm::RS256);
+            header.kid = Some(kid.to_string());
+            jsonwebtoken::encode(&header, &json!({ "gift": "lump of coal" }), &encoding_key)
+                .unwrap()
+        };
+        let request = |uri: &str, jwt: String| {
+            axum::extract::Request::builder()
+                .method("POST")
+                .uri(uri)
+                .body(axum::body::Body::from(jwt))
+                .unwrap()
+        };
+        let decode = |jwt: String| request("/decode", jwt);
+
+        let response = app.clone().oneshot(decode(sign("elf"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(!body.contains(&b'\n'));
+        let claims: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(claims, json!({ "gift": "lump of coal" }));
+
+        let response = app
+            .clone()
+            .oneshot(request("/decode?pretty=true", sign("elf")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(body.contains(&b'\n'));
+        let claims: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(claims, json!({ "gift": "lump of coal" }));
+
+        let response = app.oneshot(decode(sign("grinch"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_keys() {
+        let Json(jwks) = keys(State(Arc::new(santa_jwks()))).await;
+        let jwks: Value = serde_json::to_value(jwks).unwrap();
+
+        let keys = jwks["keys"].as_array().unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0]["kty"], "RSA");
+        assert_eq!(keys[0]["kid"], "santa");
+        assert_eq!(keys[0]["e"], "AQAB");
+
+        let jwks: JwkSet = serde_json::from_value(jwks).unwrap();
+        let AlgorithmParameters::RSA(rsa) = &jwks.keys[0].algorithm else {
+            panic!("expected an RSA key");
+        };
+        assert!(DecodingKey::from_rsa_components(&rsa.n, &rsa.e).is_ok());
+    }
+}
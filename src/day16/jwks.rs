@@ -0,0 +1,137 @@
+use std::time::{Duration, Instant};
+
+use axum::http::StatusCode;
+use jsonwebtoken::{jwk::JwkSet, DecodingKey};
+use tokio::sync::RwLock;
+use tracing::error;
+
+const JWKS_TTL: Duration = Duration::from_secs(300);
+const JWKS_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const JWKS_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Fetches the issuer's JWKS lazily and keeps it for JWKS_TTL so decoding
+// doesn't hit the issuer on every request
+pub struct JwksCache {
+    url: Option<String>,
+    client: reqwest::Client,
+    cached: RwLock<Option<(Instant, JwkSet)>>,
+}
+
+impl JwksCache {
+    pub fn new(url: Option<String>) -> Self {
+        Self::with_timeout(url, JWKS_TIMEOUT)
+    }
+
+    // A stuck issuer fails the request with 502 instead of holding it open
+    fn with_timeout(url: Option<String>, timeout: Duration) -> Self {
+        let client = reqwest::Client::builder()
+            .connect_timeout(JWKS_CONNECT_TIMEOUT.min(timeout))
+            .timeout(timeout)
+            .build()
+            .expect("jwks client must build");
+        Self {
+            url,
+            client,
+            cached: RwLock::new(None),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("SANTA_JWKS_URL").ok())
+    }
+
+    // None means no issuer is configured and the embedded key should be used
+    pub async fn decoding_key(&self, kid: Option<&str>) -> Option<Result<DecodingKey, StatusCode>> {
+        let url = self.url.as_deref()?;
+        Some(self.find(url, kid).await)
+    }
+
+    async fn find(&self, url: &str, kid: Option<&str>) -> Result<DecodingKey, StatusCode> {
+        let kid = kid.ok_or(StatusCode::UNAUTHORIZED)?;
+        let jwks = self.jwks(url).await?;
+        let jwk = jwks.find(kid).ok_or(StatusCode::UNAUTHORIZED)?;
+        DecodingKey::from_jwk(jwk)
+            .inspect_err(|err| error!(%err, kid, "jwks key was not usable"))
+            .map_err(|_| StatusCode::UNAUTHORIZED)
+    }
+
+    async fn jwks(&self, url: &str) -> Result<JwkSet, StatusCode> {
+        if let Some(jwks) = fresh(self.cached.read().await.as_ref()) {
+            return Ok(jwks);
+        }
+
+        // Whoever gets the write lock first fetches, the others find it fresh
+        let mut cached = self.cached.write().await;
+        if let Some(jwks) = fresh(cached.as_ref()) {
+            return Ok(jwks);
+        }
+        let jwks: JwkSet = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .inspect_err(|err| error!(%err, "failed to fetch jwks"))
+            .map_err(|_| StatusCode::BAD_GATEWAY)?
+            .json()
+            .await
+            .inspect_err(|err| error!(%err, "jwks response was not valid"))
+            .map_err(|_| StatusCode::BAD_GATEWAY)?;
+        *cached = Some((Instant::now(), jwks.clone()));
+        Ok(jwks)
+    }
+}
+
+fn fresh(cached: Option<&(Instant, JwkSet)>) -> Option<JwkSet> {
+    cached
+        .filter(|(fetched_at, _)| fetched_at.elapsed() < JWKS_TTL)
+        .map(|(_, jwks)| jwks.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    async fn issuer(delay: Duration, expected_fetches: u64) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/jwks.json"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(JwkSet { keys: vec![] })
+                    .set_delay(delay),
+            )
+            .expect(expected_fetches)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_misses_fetch_once() {
+        let server = issuer(Duration::from_millis(100), 1).await;
+        let cache = JwksCache::new(Some(format!("{}/jwks.json", server.uri())));
+
+        let lookups = (0..5).map(|_| cache.decoding_key(Some("elf")));
+        for result in futures::future::join_all(lookups).await {
+            assert_eq!(result.unwrap().err(), Some(StatusCode::UNAUTHORIZED));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_issuer_times_out() {
+        let server = issuer(Duration::from_secs(5), 1).await;
+        let cache = JwksCache::with_timeout(
+            Some(format!("{}/jwks.json", server.uri())),
+            Duration::from_millis(100),
+        );
+
+        let result = cache.decoding_key(Some("elf")).await.unwrap();
+        assert_eq!(result.err(), Some(StatusCode::BAD_GATEWAY));
+    }
+}
@@ -1,115 +1,485 @@
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::Result,
-    routing::{delete, get, post, put},
-    Json,
+    extract::{FromRef, OriginalUri, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{delete, get, patch, post, put},
+    BoxError, Json,
 };
-use chrono::DateTime;
+use cursor::{Cursor, CursorStore};
+use error::ApiError;
+use extract::{ApiJson, ApiPath, ApiQuery};
+use futures::{SinkExt, StreamExt};
 use rand::Rng;
+use retry::with_retry;
 use serde::{Deserialize, Serialize};
-use sqlx::{types::uuid, PgPool};
+use serde_json::Value;
+use sqlx::{types::uuid, PgPool, Postgres, Transaction};
 use tracing::debug;
+use utoipa::{IntoParams, OpenApi, ToSchema};
 use uuid::Uuid;
 
-#[derive(Deserialize, Serialize, PartialEq, Debug)]
+mod cursor;
+mod error;
+mod extract;
+mod retry;
+
+#[derive(Deserialize, Serialize, ToSchema, PartialEq, Debug)]
 struct Quote {
     id: Uuid,
     author: String,
     quote: String,
     created_at: chrono::DateTime<chrono::Utc>,
     version: i32,
+    likes: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deleted_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, ToSchema, Debug)]
 struct DraftQuote {
     author: String,
     quote: String,
 }
 
-#[derive(Deserialize, Serialize)]
-struct QuoteList {
-    quotes: Vec<Quote>,
+// Duplicates are allowed unless the caller opts in to the check
+#[derive(Deserialize, IntoParams, Default)]
+#[into_params(parameter_in = Query)]
+struct DraftQuery {
+    #[serde(default)]
+    dedupe: bool,
+}
+
+#[derive(Deserialize, Serialize, ToSchema, Default)]
+struct PartialQuote {
+    author: Option<String>,
+    quote: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct QuoteList<T = Quote> {
+    quotes: Vec<T>,
     page: i32,
     next_token: Option<String>,
+    total: i64,
+    total_pages: i32,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct QuoteListQuery {
-    token: String,
+    token: Option<String>,
+    #[serde(default)]
+    sort: ListSort,
+    #[serde(default)]
+    order: ListOrder,
+    author: Option<String>,
+    after: Option<chrono::DateTime<chrono::Utc>>,
+    before: Option<chrono::DateTime<chrono::Utc>>,
+    page: Option<i32>,
+    limit: Option<i32>,
+    fields: Option<String>,
+    // Adds a ready-made `next` URL alongside the raw next_token
+    #[serde(default)]
+    links: bool,
 }
 
-struct Cursor {
-    token: String,
-    page: i32,
-    created_at: DateTime<chrono::Utc>,
+#[derive(Deserialize, IntoParams, Default)]
+#[into_params(parameter_in = Query)]
+struct RemoveQuery {
+    #[serde(default)]
+    hard: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SearchQuery {
+    q: String,
+    #[serde(default)]
+    rank: bool,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct SearchResult {
+    #[serde(flatten)]
+    quote: Quote,
+    headline: Option<String>,
+}
+
+const DEFAULT_PAGE_SIZE: i32 = 3;
+const MAX_PAGE_SIZE: i32 = 50;
+
+// Every key a serialized Quote can have, test_quote_fields keeps the two in step
+const QUOTE_FIELDS: &[&str] = &[
+    "id",
+    "author",
+    "quote",
+    "created_at",
+    "version",
+    "likes",
+    "deleted_at",
+];
+
+#[derive(Deserialize, Serialize, ToSchema, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+enum ListSort {
+    #[default]
+    CreatedAt,
+    Likes,
+}
+
+impl ListSort {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ListSort::CreatedAt => "created_at",
+            ListSort::Likes => "likes",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+enum ListOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl ListOrder {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ListOrder::Asc => "asc",
+            ListOrder::Desc => "desc",
+        }
+    }
+}
+
+#[derive(Clone)]
+struct AppState {
+    pool: PgPool,
+    cursors: CursorStore,
+}
+
+impl FromRef<AppState> for PgPool {
+    fn from_ref(state: &AppState) -> Self {
+        state.pool.clone()
+    }
+}
+
+impl FromRef<AppState> for CursorStore {
+    fn from_ref(state: &AppState) -> Self {
+        state.cursors.clone()
+    }
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    reset,
+    cite,
+    remove,
+    remove_quote,
+    remove_batch,
+    undo,
+    edit,
+    draft,
+    import,
+    list,
+    search,
+    count,
+    authors,
+    export_csv,
+    stream,
+    random,
+    trash,
+    restore,
+    history,
+))]
+pub struct ApiDoc;
+
 pub fn router(pool: PgPool) -> axum::Router {
+    router_with_cursors(pool, CursorStore::from_env())
+}
+
+fn router_with_cursors(pool: PgPool, cursors: CursorStore) -> axum::Router {
     axum::Router::new()
         .route("/reset", post(reset))
         .route("/cite/:id", get(cite))
         .route("/remove/:id", delete(remove))
+        .route("/quote/:id", delete(remove_quote))
+        .route("/remove-batch", post(remove_batch))
         .route("/undo/:id", put(undo))
+        .route("/edit/:id", patch(edit))
         .route("/draft", post(draft))
+        .route("/import", post(import))
         .route("/list", get(list))
-        .with_state(pool)
+        .route("/search", get(search))
+        .route("/count", get(count))
+        .route("/authors", get(authors))
+        .route("/export.csv", get(export_csv))
+        .route("/stream", get(stream))
+        .route("/random", get(random))
+        .route("/trash", get(trash))
+        .route("/restore/:id", post(restore))
+        .route("/history/:id", get(history))
+        .with_state(AppState { pool, cursors })
 }
 
-async fn reset(State(state): State<PgPool>) -> Result<StatusCode> {
-    sqlx::query!("DELETE FROM quotes")
-        .execute(&state)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    sqlx::query!("DELETE FROM cursors")
+#[utoipa::path(
+    post,
+    path = "/reset",
+    responses((status = OK, description = "Every quote was deleted and every cursor invalidated")),
+)]
+async fn reset(State(state): State<PgPool>) -> Result<StatusCode, ApiError> {
+    sqlx::query!("DELETE FROM quotes").execute(&state).await?;
+    CursorStore::clear(&state).await?;
+    sqlx::query!("DELETE FROM quote_versions")
         .execute(&state)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .await?;
     Ok(StatusCode::OK)
 }
 
+#[utoipa::path(
+    get,
+    path = "/cite/{id}",
+    params(("id" = Uuid, Path, description = "Quote id")),
+    responses(
+        (status = OK, body = Quote),
+        (status = NOT_FOUND),
+    ),
+)]
 async fn cite(
-    Path(id): Path<Uuid>,
+    ApiPath(id): ApiPath<Uuid>,
     State(state): State<PgPool>,
-) -> Result<Json<Quote>, StatusCode> {
-    let quote = sqlx::query_as!(Quote, "SELECT * FROM quotes WHERE id = $1", id)
+) -> Result<Json<Quote>, ApiError> {
+    let quote = with_retry(|| {
+        sqlx::query_as!(
+            Quote,
+            "SELECT * FROM quotes WHERE id = $1 AND deleted_at IS NULL",
+            id
+        )
         .fetch_one(&state)
-        .await
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    })
+    .await?;
     Ok(Json(quote))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/remove/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Quote id"),
+        RemoveQuery,
+    ),
+    responses(
+        (status = OK, body = Quote),
+        (status = NOT_FOUND),
+    ),
+)]
 async fn remove(
-    Path(id): Path<Uuid>,
+    ApiPath(id): ApiPath<Uuid>,
     State(state): State<PgPool>,
-) -> Result<Json<Quote>, StatusCode> {
-    let quote = sqlx::query_as!(Quote, "SELECT * FROM quotes WHERE id = $1", id)
-        .fetch_one(&state)
+    ApiQuery(query): ApiQuery<RemoveQuery>,
+) -> Result<Json<Quote>, ApiError> {
+    delete_quote(id, query.hard, &state).await.map(Json)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/quote/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Quote id"),
+        RemoveQuery,
+    ),
+    responses(
+        (status = NO_CONTENT),
+        (status = NOT_FOUND),
+    ),
+)]
+async fn remove_quote(
+    ApiPath(id): ApiPath<Uuid>,
+    State(state): State<PgPool>,
+    ApiQuery(query): ApiQuery<RemoveQuery>,
+) -> Result<StatusCode, ApiError> {
+    delete_quote(id, query.hard, &state).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Soft deletes only hide the quote, it stays in the trash until restored
+async fn delete_quote(id: Uuid, hard: bool, state: &PgPool) -> Result<Quote, ApiError> {
+    let quote = if hard {
+        sqlx::query_as!(Quote, "DELETE FROM quotes WHERE id = $1 RETURNING *", id)
+            .fetch_optional(state)
+            .await
+    } else {
+        sqlx::query_as!(
+            Quote,
+            "UPDATE quotes SET deleted_at = NOW()
+             WHERE id = $1 AND deleted_at IS NULL
+             RETURNING *",
+            id
+        )
+        .fetch_optional(state)
         .await
-        .map_err(|_| StatusCode::NOT_FOUND)?;
-    sqlx::query!("DELETE FROM quotes WHERE id = $1", id)
+    };
+    quote?.ok_or(ApiError::NotFound)
+}
+
+#[utoipa::path(
+    get,
+    path = "/random",
+    responses(
+        (status = OK, body = Quote),
+        (status = NOT_FOUND),
+    ),
+)]
+async fn random(State(state): State<PgPool>) -> Result<Json<Quote>, ApiError> {
+    let quote = sqlx::query_as!(
+        Quote,
+        "SELECT * FROM quotes WHERE deleted_at IS NULL ORDER BY RANDOM() LIMIT 1"
+    )
+    .fetch_optional(&state)
+    .await?
+    .ok_or(ApiError::NotFound)?;
+    Ok(Json(quote))
+}
+
+#[derive(Deserialize, Serialize, ToSchema, PartialEq, Debug)]
+struct QuoteVersion {
+    version: i32,
+    author: String,
+    quote: String,
+    recorded_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct DeletedCount {
+    deleted: u64,
+}
+
+// Ids that don't match a live quote are skipped rather than failing the batch
+#[utoipa::path(
+    post,
+    path = "/remove-batch",
+    params(RemoveQuery),
+    request_body = Vec<Uuid>,
+    responses((status = OK, body = DeletedCount)),
+)]
+async fn remove_batch(
+    State(state): State<PgPool>,
+    ApiQuery(query): ApiQuery<RemoveQuery>,
+    ApiJson(ids): ApiJson<Vec<Uuid>>,
+) -> Result<Json<DeletedCount>, ApiError> {
+    let result = if query.hard {
+        sqlx::query!("DELETE FROM quotes WHERE id = ANY($1)", &ids)
+            .execute(&state)
+            .await
+    } else {
+        sqlx::query!(
+            "UPDATE quotes SET deleted_at = NOW() WHERE id = ANY($1) AND deleted_at IS NULL",
+            &ids
+        )
         .execute(&state)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    };
+    let deleted = result?.rows_affected();
+    Ok(Json(DeletedCount { deleted }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/trash",
+    responses((status = OK, body = Vec<Quote>)),
+)]
+async fn trash(State(state): State<PgPool>) -> Result<Json<Vec<Quote>>, ApiError> {
+    let quotes = sqlx::query_as!(
+        Quote,
+        "SELECT * FROM quotes WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC"
+    )
+    .fetch_all(&state)
+    .await?;
+    Ok(Json(quotes))
+}
+
+#[utoipa::path(
+    post,
+    path = "/restore/{id}",
+    params(("id" = Uuid, Path, description = "Quote id")),
+    responses(
+        (status = OK, body = Quote),
+        (status = NOT_FOUND),
+    ),
+)]
+async fn restore(
+    ApiPath(id): ApiPath<Uuid>,
+    State(state): State<PgPool>,
+) -> Result<Json<Quote>, ApiError> {
+    let quote = sqlx::query_as!(
+        Quote,
+        "UPDATE quotes SET deleted_at = NULL
+         WHERE id = $1 AND deleted_at IS NOT NULL
+         RETURNING *",
+        id
+    )
+    .fetch_optional(&state)
+    .await?
+    .ok_or(ApiError::NotFound)?;
     Ok(Json(quote))
 }
 
+// History outlives hard deletes, so only ids that were never used are missing
+#[utoipa::path(
+    get,
+    path = "/history/{id}",
+    params(("id" = Uuid, Path, description = "Quote id")),
+    responses(
+        (status = OK, body = Vec<QuoteVersion>),
+        (status = NOT_FOUND),
+    ),
+)]
+async fn history(
+    ApiPath(id): ApiPath<Uuid>,
+    State(state): State<PgPool>,
+) -> Result<Json<Vec<QuoteVersion>>, ApiError> {
+    let known = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM quotes WHERE id = $1)
+               OR EXISTS(SELECT 1 FROM quote_versions WHERE quote_id = $1) AS "known!""#,
+        id
+    )
+    .fetch_one(&state)
+    .await?;
+    if !known {
+        return Err(ApiError::NotFound);
+    }
+
+    let versions = sqlx::query_as!(
+        QuoteVersion,
+        "SELECT version, author, quote, recorded_at FROM quote_versions
+         WHERE quote_id = $1
+         ORDER BY version DESC",
+        id
+    )
+    .fetch_all(&state)
+    .await?;
+    Ok(Json(versions))
+}
+
+#[utoipa::path(
+    put,
+    path = "/undo/{id}",
+    params(("id" = Uuid, Path, description = "Quote id")),
+    request_body = DraftQuote,
+    responses(
+        (status = OK, body = Quote),
+        (status = NOT_FOUND),
+    ),
+)]
 async fn undo(
-    Path(id): Path<Uuid>,
+    ApiPath(id): ApiPath<Uuid>,
     State(state): State<PgPool>,
-    Json(update): Json<DraftQuote>,
-) -> Result<Json<Quote>, StatusCode> {
-    // Fetch the quote
-    let mut quote = sqlx::query_as!(Quote, "SELECT * FROM quotes WHERE id = $1", id)
-        .fetch_one(&state)
-        .await
-        .map_err(|error| {
-            if let sqlx::Error::RowNotFound = error {
-                StatusCode::NOT_FOUND
-            } else {
-                StatusCode::INTERNAL_SERVER_ERROR
-            }
-        })?;
+    ApiJson(update): ApiJson<DraftQuote>,
+) -> Result<Json<Quote>, ApiError> {
+    let mut transaction = state.begin().await?;
+    let mut quote = lock_quote(&mut transaction, id).await?;
+    record_version(&mut transaction, &quote).await?;
 
     // Update the quote struct
     quote.author = update.author;
@@ -126,17 +496,120 @@ async fn undo(
         quote.version,
         quote.id,
     )
-    .execute(&state)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+
+    Ok(Json(quote))
+}
+
+// Holds the row lock until the transaction ends, so concurrent edits record their
+// versions one after another instead of racing for the same version number
+async fn lock_quote(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: Uuid,
+) -> Result<Quote, ApiError> {
+    let quote = sqlx::query_as!(
+        Quote,
+        "SELECT * FROM quotes WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+        id
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+    Ok(quote)
+}
+
+async fn record_version(
+    transaction: &mut Transaction<'_, Postgres>,
+    quote: &Quote,
+) -> Result<(), ApiError> {
+    sqlx::query!(
+        "INSERT INTO quote_versions (quote_id, version, author, quote)
+         VALUES ($1, $2, $3, $4)",
+        quote.id,
+        quote.version,
+        quote.author,
+        quote.quote,
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(())
+}
+
+#[utoipa::path(
+    patch,
+    path = "/edit/{id}",
+    params(("id" = Uuid, Path, description = "Quote id")),
+    request_body = PartialQuote,
+    responses(
+        (status = OK, body = Quote),
+        (status = BAD_REQUEST),
+        (status = NOT_FOUND),
+    ),
+)]
+async fn edit(
+    ApiPath(id): ApiPath<Uuid>,
+    State(state): State<PgPool>,
+    ApiJson(update): ApiJson<PartialQuote>,
+) -> Result<Json<Quote>, ApiError> {
+    if update.author.is_none() && update.quote.is_none() {
+        return Err(ApiError::BadRequest("nothing to update"));
+    }
+
+    let mut transaction = state.begin().await?;
+    let prior = lock_quote(&mut transaction, id).await?;
+    record_version(&mut transaction, &prior).await?;
+
+    // Only overwrite the fields that were provided
+    let quote = sqlx::query_as!(
+        Quote,
+        "UPDATE quotes
+         SET author = COALESCE($1, author), quote = COALESCE($2, quote), version = version + 1
+         WHERE id = $3
+         RETURNING *",
+        update.author,
+        update.quote,
+        id,
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
 
     Ok(Json(quote))
 }
 
+#[utoipa::path(
+    post,
+    path = "/draft",
+    params(DraftQuery),
+    request_body = DraftQuote,
+    responses(
+        (status = CREATED, body = Quote),
+        (status = CONFLICT, body = Quote, description = "An identical quote already exists"),
+    ),
+)]
 async fn draft(
     State(state): State<PgPool>,
-    Json(draft): Json<DraftQuote>,
-) -> Result<(StatusCode, Json<Quote>), StatusCode> {
+    ApiQuery(query): ApiQuery<DraftQuery>,
+    ApiJson(draft): ApiJson<DraftQuote>,
+) -> Result<(StatusCode, Json<Quote>), ApiError> {
+    if query.dedupe {
+        let existing = sqlx::query_as!(
+            Quote,
+            "SELECT * FROM quotes
+             WHERE author = $1 AND quote = $2 AND deleted_at IS NULL
+             ORDER BY created_at ASC
+             LIMIT 1",
+            draft.author,
+            draft.quote,
+        )
+        .fetch_optional(&state)
+        .await?;
+        if let Some(existing) = existing {
+            return Ok((StatusCode::CONFLICT, Json(existing)));
+        }
+    }
+
     let id = Uuid::new_v4();
     // Insert the quote into the database
     sqlx::query!(
@@ -147,126 +620,491 @@ async fn draft(
         draft.quote,
     )
     .execute(&state)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .await?;
 
     debug!("inserted quote with id: {:?}", id);
 
     // Return the quote
-    cite(Path(id), State(state))
+    cite(ApiPath(id), State(state))
         .await
         .map(|quote| (StatusCode::CREATED, quote))
 }
 
+#[utoipa::path(
+    post,
+    path = "/import",
+    request_body = Vec<DraftQuote>,
+    responses((status = CREATED, body = Vec<Quote>)),
+)]
+async fn import(
+    State(state): State<PgPool>,
+    ApiJson(drafts): ApiJson<Vec<DraftQuote>>,
+) -> Result<(StatusCode, Json<Vec<Quote>>), ApiError> {
+    // Dropping the transaction on an early return rolls back every insert
+    let mut transaction = state.begin().await?;
+
+    let mut quotes = Vec::with_capacity(drafts.len());
+    for draft in drafts {
+        // clock_timestamp keeps input order, NOW() is fixed for the whole transaction
+        let quote = sqlx::query_as!(
+            Quote,
+            "INSERT INTO quotes (id, author, quote, created_at)
+             VALUES ($1, $2, $3, clock_timestamp())
+             RETURNING *",
+            Uuid::new_v4(),
+            draft.author,
+            draft.quote,
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+        quotes.push(quote);
+    }
+
+    transaction.commit().await?;
+    debug!("imported {} quotes", quotes.len());
+
+    Ok((StatusCode::CREATED, Json(quotes)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/list",
+    params(QuoteListQuery),
+    responses(
+        (status = OK, body = QuoteList),
+        (status = BAD_REQUEST),
+    ),
+)]
 async fn list(
     State(state): State<PgPool>,
-    query: Option<Query<QuoteListQuery>>,
-) -> Result<Json<QuoteList>, StatusCode> {
-    match query {
-        Some(Query(QuoteListQuery { token })) => list_with_token(token, state).await,
-        None => list_new(state).await,
+    State(cursors): State<CursorStore>,
+    OriginalUri(uri): OriginalUri,
+    ApiQuery(query): ApiQuery<QuoteListQuery>,
+) -> Result<Response, ApiError> {
+    let fields: Option<Vec<&str>> = query
+        .fields
+        .as_deref()
+        .map(|fields| fields.split(',').map(str::trim).collect());
+    if let Some(fields) = &fields {
+        if !fields.iter().all(|field| QUOTE_FIELDS.contains(field)) {
+            return Err(ApiError::BadRequest("unknown quote field"));
+        }
+    }
+
+    let Json(list) = match (&query.token, query.page) {
+        (Some(_), Some(_)) => {
+            return Err(ApiError::BadRequest("page can't be combined with a token"))
+        }
+        // The token's page size wins over any limit passed alongside it
+        (Some(token), None) => list_with_token(token.clone(), state, cursors).await,
+        (None, page) => {
+            let page = page.unwrap_or(1);
+            if page < 1 {
+                return Err(ApiError::BadRequest("page starts at 1"));
+            }
+            list_new(&query, page, state, cursors).await
+        }
+    }?;
+
+    if fields.is_none() && !query.links {
+        return Ok(Json(list).into_response());
     }
+    // The path comes from the original request so the link survives nesting under /19
+    let next = list
+        .next_token
+        .as_ref()
+        .map(|token| format!("{}?token={token}", uri.path()));
+    let mut list = serde_json::to_value(list).map_err(|_| ApiError::Internal)?;
+    if query.links {
+        list["next"] = next.into();
+    }
+    if let Some(fields) = fields {
+        if let Some(quotes) = list["quotes"].as_array_mut() {
+            for quote in quotes.iter_mut().filter_map(Value::as_object_mut) {
+                quote.retain(|key, _| fields.contains(&key.as_str()));
+            }
+        }
+    }
+    Ok(Json(list).into_response())
 }
 
-async fn list_new(state: PgPool) -> Result<Json<QuoteList>, StatusCode> {
-    let mut quotes = sqlx::query_as!(
-        Quote,
-        "SELECT * FROM quotes ORDER BY created_at ASC LIMIT 4"
-    )
-    .fetch_all(&state)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+// Starts a listing at any 1-based page, handing out a token for the pages after it
+async fn list_new(
+    query: &QuoteListQuery,
+    page: i32,
+    state: PgPool,
+    cursors: CursorStore,
+) -> Result<Json<QuoteList>, ApiError> {
+    let page_size = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let offset = page_offset(page - 1, page_size)?;
+    // One extra row tells us whether there is another page
+    let mut quotes = with_retry(|| {
+        sqlx::query_as!(
+            Quote,
+            "SELECT * FROM quotes
+             WHERE deleted_at IS NULL AND ($3::TEXT IS NULL OR author = $3)
+               AND ($4::TIMESTAMPTZ IS NULL OR created_at >= $4)
+               AND ($5::TIMESTAMPTZ IS NULL OR created_at < $5)
+             ORDER BY CASE WHEN $1 = 'likes' THEN likes END DESC,
+                      CASE WHEN $2 = 'desc' THEN created_at END DESC,
+                      created_at ASC
+             OFFSET $6 LIMIT $7 + 1",
+            query.sort.as_str(),
+            query.order.as_str(),
+            query.author,
+            query.after,
+            query.before,
+            offset,
+            page_size,
+        )
+        .fetch_all(&state)
+    })
+    .await?;
 
-    let last_quote = quotes.get(3);
+    let last_quote = quotes.get(page_size as usize);
 
     let next_token = match last_quote {
         Some(quote) => {
             let cursor = Cursor {
                 token: generate_random_ascii_string(16),
-                page: 1,
+                page,
                 created_at: quote.created_at,
+                sort: query.sort.as_str().to_string(),
+                direction: query.order.as_str().to_string(),
+                author: query.author.clone(),
+                created_after: query.after,
+                created_before: query.before,
+                page_size,
             };
-            sqlx::query!(
-                "INSERT INTO cursors (token, created_at) VALUES ($1, $2)",
-                cursor.token,
-                cursor.created_at
-            )
-            .execute(&state)
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-            Some(cursor.token)
+            Some(cursors.create(&state, &cursor).await?)
         }
         None => None,
     };
 
-    // Remove the last quote from the list if we got 4 entries
+    // Remove the lookahead quote if we got one
     if last_quote.is_some() {
         quotes.pop();
     }
 
+    let total = quote_total(&state, query.author.as_deref(), query.after, query.before).await?;
     let list = QuoteList {
         quotes,
-        page: 1,
+        page,
         next_token,
+        total,
+        total_pages: total_pages(total, page_size),
     };
 
     Ok(Json(list))
 }
 
-async fn list_with_token(token: String, state: PgPool) -> Result<Json<QuoteList>, StatusCode> {
-    let cursor = sqlx::query_as!(
-        Cursor,
-        "SELECT token, page, created_at FROM cursors WHERE token = $1",
-        token
-    )
-    .fetch_optional(&state)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-    .ok_or(StatusCode::BAD_REQUEST)?;
+async fn list_with_token(
+    token: String,
+    state: PgPool,
+    cursors: CursorStore,
+) -> Result<Json<QuoteList>, ApiError> {
+    let cursor = cursors.load(&state, &token).await?;
 
     let page = cursor.page;
+    let page_size = cursor.page_size;
+    let offset = page_offset(page, page_size)?;
 
-    let quotes = sqlx::query_as!(
-        Quote,
-        "SELECT * FROM quotes ORDER BY created_at ASC OFFSET $1 * 3 LIMIT 4",
-        page,
-    )
-    .fetch_all(&state)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut quotes = with_retry(|| {
+        sqlx::query_as!(
+            Quote,
+            "SELECT * FROM quotes
+             WHERE deleted_at IS NULL AND ($4::TEXT IS NULL OR author = $4)
+               AND ($5::TIMESTAMPTZ IS NULL OR created_at >= $5)
+               AND ($6::TIMESTAMPTZ IS NULL OR created_at < $6)
+             ORDER BY CASE WHEN $2 = 'likes' THEN likes END DESC,
+                      CASE WHEN $3 = 'desc' THEN created_at END DESC,
+                      created_at ASC
+             OFFSET $1 LIMIT $7 + 1",
+            offset,
+            cursor.sort,
+            cursor.direction,
+            cursor.author,
+            cursor.created_after,
+            cursor.created_before,
+            page_size,
+        )
+        .fetch_all(&state)
+    })
+    .await?;
 
-    let last_quote = quotes.get(3);
+    let last_quote = quotes.get(page_size as usize);
+    let total = quote_total(
+        &state,
+        cursor.author.as_deref(),
+        cursor.created_after,
+        cursor.created_before,
+    )
+    .await?;
 
     let next_token = match last_quote {
-        Some(_) => {
-            sqlx::query!(
-                "UPDATE cursors SET page = $1 WHERE token = $2",
-                cursor.page + 1,
-                cursor.token
-            )
-            .execute(&state)
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-            Some(cursor.token)
-        }
+        Some(_) => Some(cursors.advance(&state, cursor).await?),
         None => {
-            sqlx::query!("DELETE FROM cursors WHERE token = $1", cursor.token)
-                .execute(&state)
-                .await
-                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            cursors.finish(&state, cursor).await?;
             None
         }
     };
 
+    // Remove the lookahead quote if we got one
+    quotes.truncate(page_size as usize);
+
     let list = QuoteList {
         quotes,
         page: page + 1,
         next_token,
+        total,
+        total_pages: total_pages(total, page_size),
     };
 
     Ok(Json(list))
 }
 
+#[utoipa::path(
+    get,
+    path = "/search",
+    params(SearchQuery),
+    responses(
+        (status = OK, body = QuoteList<SearchResult>),
+        (status = BAD_REQUEST),
+    ),
+)]
+async fn search(
+    State(state): State<PgPool>,
+    ApiQuery(query): ApiQuery<SearchQuery>,
+) -> Result<Json<QuoteList<SearchResult>>, ApiError> {
+    if query.q.trim().is_empty() {
+        return Err(ApiError::BadRequest("search query is empty"));
+    }
+
+    let results = if query.rank {
+        search_ranked(&query.q, &state).await?
+    } else {
+        search_matching(&query.q, &state).await?
+    };
+    // Search results are not paginated yet, so everything is on the first page
+    let total = results.len() as i64;
+    Ok(Json(QuoteList {
+        quotes: results,
+        page: 1,
+        next_token: None,
+        total,
+        total_pages: i32::from(total > 0),
+    }))
+}
+
+async fn search_matching(q: &str, state: &PgPool) -> Result<Vec<SearchResult>, ApiError> {
+    // Escape LIKE wildcards so the query is matched literally
+    let pattern = q
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    let quotes = sqlx::query_as!(
+        Quote,
+        "SELECT * FROM quotes
+         WHERE deleted_at IS NULL
+           AND (author ILIKE '%' || $1 || '%' OR quote ILIKE '%' || $1 || '%')
+         ORDER BY created_at ASC",
+        pattern,
+    )
+    .fetch_all(state)
+    .await?;
+
+    let results = quotes
+        .into_iter()
+        .map(|quote| SearchResult {
+            quote,
+            headline: None,
+        })
+        .collect();
+    Ok(results)
+}
+
+async fn search_ranked(q: &str, state: &PgPool) -> Result<Vec<SearchResult>, ApiError> {
+    let rows = sqlx::query!(
+        r#"SELECT id, author, quote, created_at, version, likes, deleted_at,
+             ts_headline('english', quote, plainto_tsquery('english', $1),
+                         'StartSel=<b>, StopSel=</b>') AS "headline!"
+           FROM quotes
+           WHERE deleted_at IS NULL
+             AND to_tsvector('english', author || ' ' || quote) @@ plainto_tsquery('english', $1)
+           ORDER BY ts_rank(to_tsvector('english', author || ' ' || quote),
+                            plainto_tsquery('english', $1)) DESC,
+                    created_at ASC"#,
+        q,
+    )
+    .fetch_all(state)
+    .await?;
+
+    let results = rows
+        .into_iter()
+        .map(|row| SearchResult {
+            quote: Quote {
+                id: row.id,
+                author: row.author,
+                quote: row.quote,
+                created_at: row.created_at,
+                version: row.version,
+                likes: row.likes,
+                deleted_at: row.deleted_at,
+            },
+            headline: Some(row.headline),
+        })
+        .collect();
+    Ok(results)
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct QuoteCount {
+    count: i64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/count",
+    responses((status = OK, body = QuoteCount)),
+)]
+async fn count(State(state): State<PgPool>) -> Result<Json<QuoteCount>, ApiError> {
+    let count = quote_total(&state, None, None, None).await?;
+    Ok(Json(QuoteCount { count }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/authors",
+    responses((status = OK, body = Vec<String>)),
+)]
+async fn authors(State(state): State<PgPool>) -> Result<Json<Vec<String>>, ApiError> {
+    let authors = sqlx::query_scalar!(
+        "SELECT DISTINCT author FROM quotes WHERE deleted_at IS NULL ORDER BY author"
+    )
+    .fetch_all(&state)
+    .await?;
+    Ok(Json(authors))
+}
+
+// Rows are written out as they arrive, so the full set is never held in memory
+#[utoipa::path(
+    get,
+    path = "/stream",
+    responses((status = OK, body = Quote, content_type = "application/x-ndjson")),
+)]
+async fn stream(State(state): State<PgPool>) -> Response {
+    let (mut sender, receiver) = futures::channel::mpsc::channel::<Result<String, BoxError>>(16);
+    tokio::spawn(async move {
+        let mut quotes = sqlx::query_as!(
+            Quote,
+            "SELECT * FROM quotes WHERE deleted_at IS NULL ORDER BY created_at ASC"
+        )
+        .fetch(&state);
+        while let Some(quote) = quotes.next().await {
+            let line = quote.map_err(BoxError::from).and_then(|quote| {
+                let mut line = serde_json::to_string(&quote)?;
+                line.push('\n');
+                Ok(line)
+            });
+            // A failed send means the client went away
+            if sender.send(line).await.is_err() {
+                break;
+            }
+        }
+    });
+    (
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        axum::body::Body::from_stream(receiver),
+    )
+        .into_response()
+}
+
+// Streamed like /stream, one CSV record per row
+#[utoipa::path(
+    get,
+    path = "/export.csv",
+    responses((status = OK, body = String, content_type = "text/csv")),
+)]
+async fn export_csv(State(state): State<PgPool>) -> Response {
+    let (mut sender, receiver) = futures::channel::mpsc::channel::<Result<Vec<u8>, BoxError>>(16);
+    tokio::spawn(async move {
+        let header = csv_record(["id", "author", "quote", "created_at", "version"]);
+        if sender.send(header).await.is_err() {
+            return;
+        }
+        let mut quotes = sqlx::query_as!(
+            Quote,
+            "SELECT * FROM quotes WHERE deleted_at IS NULL ORDER BY created_at ASC"
+        )
+        .fetch(&state);
+        while let Some(quote) = quotes.next().await {
+            let record = quote.map_err(BoxError::from).and_then(|quote| {
+                csv_record([
+                    quote.id.to_string(),
+                    quote.author,
+                    quote.quote,
+                    quote.created_at.to_rfc3339(),
+                    quote.version.to_string(),
+                ])
+            });
+            // A failed send means the client went away
+            if sender.send(record).await.is_err() {
+                break;
+            }
+        }
+    });
+    (
+        [(header::CONTENT_TYPE, "text/csv")],
+        axum::body::Body::from_stream(receiver),
+    )
+        .into_response()
+}
+
+// Each record gets its own writer so it can be sent as soon as it is quoted
+fn csv_record<T: AsRef<[u8]>>(record: impl IntoIterator<Item = T>) -> Result<Vec<u8>, BoxError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(record)?;
+    Ok(writer.into_inner().map_err(|err| err.into_error())?)
+}
+
+// The created_at range is half-open: after is inclusive, before is exclusive
+async fn quote_total(
+    state: &PgPool,
+    author: Option<&str>,
+    after: Option<chrono::DateTime<chrono::Utc>>,
+    before: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<i64, ApiError> {
+    with_retry(|| {
+        sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM quotes
+               WHERE deleted_at IS NULL AND ($1::TEXT IS NULL OR author = $1)
+                 AND ($2::TIMESTAMPTZ IS NULL OR created_at >= $2)
+                 AND ($3::TIMESTAMPTZ IS NULL OR created_at < $3)"#,
+            author,
+            after,
+            before,
+        )
+        .fetch_one(state)
+    })
+    .await
+    .map_err(ApiError::from)
+}
+
+// Pages far enough out to overflow the offset can't hold any quotes anyway
+fn page_offset(page: i32, page_size: i32) -> Result<i64, ApiError> {
+    page.checked_mul(page_size)
+        .map(i64::from)
+        .ok_or(ApiError::BadRequest("page is out of range"))
+}
+
+fn total_pages(total: i64, page_size: i32) -> i32 {
+    let page_size = i64::from(page_size);
+    ((total + page_size - 1) / page_size) as i32
+}
+
 fn generate_random_ascii_string(length: usize) -> String {
     rand::thread_rng()
         .sample_iter(&rand::distributions::Alphanumeric)
@@ -281,6 +1119,7 @@ mod tests {
 
     use axum::{body::Body, extract::Request, http::header::CONTENT_TYPE};
     use http_body_util::BodyExt;
+    use serde_json::json;
     use tower::{Service, ServiceExt};
 
     #[sqlx::test]
@@ -317,6 +1156,37 @@ mod tests {
         assert_eq!(db_quote, quote);
     }
 
+    #[sqlx::test]
+    async fn test_import(pool: PgPool) {
+        let app = router(pool.clone());
+        let drafts: Vec<DraftQuote> = ["FOO", "BAR", "BAZ"]
+            .into_iter()
+            .map(|author| DraftQuote {
+                author: author.to_string(),
+                quote: format!("{author} was here."),
+            })
+            .collect();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/import")
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(Body::from(serde_json::to_vec(&drafts).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let imported: Vec<Quote> = serde_json::from_slice(&body).unwrap();
+        let authors: Vec<&str> = imported.iter().map(|quote| quote.author.as_str()).collect();
+        assert_eq!(authors, vec!["FOO", "BAR", "BAZ"]);
+        assert_eq!(get_quotes(&pool).await, imported);
+    }
+
     #[sqlx::test(fixtures("quotes_3"))]
     async fn test_list(pool: PgPool) {
         let quotes = get_quotes(&pool).await;
@@ -335,6 +1205,35 @@ mod tests {
         assert_eq!(quotes, list.quotes);
         assert_eq!(1, list.page);
         assert!(list.next_token.is_none());
+        assert_eq!(3, list.total);
+        assert_eq!(1, list.total_pages);
+    }
+
+    #[sqlx::test(fixtures("quotes_4"))]
+    async fn test_list_links(pool: PgPool) {
+        let app = axum::Router::new().nest("/19", router(pool));
+        async fn get_list(app: &axum::Router, uri: &str) -> Value {
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            serde_json::from_slice(&body).unwrap()
+        }
+
+        let list = get_list(&app, "/19/list").await;
+        assert!(list.get("next").is_none());
+
+        let list = get_list(&app, "/19/list?links=true").await;
+        let token = list["next_token"].as_str().unwrap();
+        let next = list["next"].as_str().unwrap();
+        assert_eq!(next, format!("/19/list?token={token}"));
+
+        let list = get_list(&app, next).await;
+        assert_eq!(list["page"], 2);
+        assert!(list["next_token"].is_null());
+
+        let list = get_list(&app, "/19/list?page=2&links=true").await;
+        assert!(list["next"].is_null());
     }
 
     #[sqlx::test(fixtures("quotes_4"))]
@@ -372,6 +1271,26 @@ mod tests {
         assert_eq!(&quotes[3..], &list.quotes);
         assert_eq!(2, list.page);
         assert_eq!(None, list.next_token);
+        assert_eq!(4, list.total);
+        assert_eq!(2, list.total_pages);
+    }
+
+    #[sqlx::test(fixtures("quotes_4"))]
+    async fn test_list_totals(pool: PgPool) {
+        let mut app = router(pool);
+
+        let list = list_page(&mut app, "/list").await;
+        assert_eq!((1, 4, 2), (list.page, list.total, list.total_pages));
+
+        let token = list.next_token.unwrap();
+        let list = list_page(&mut app, &format!("/list?token={token}")).await;
+        assert_eq!((2, 4, 2), (list.page, list.total, list.total_pages));
+
+        assert_eq!(total_pages(0, 3), 0);
+        assert_eq!(total_pages(3, 3), 1);
+        assert_eq!(total_pages(7, 3), 3);
+        assert_eq!(total_pages(7, 1), 7);
+        assert_eq!(total_pages(7, 10), 1);
     }
 
     #[sqlx::test]
@@ -434,6 +1353,1038 @@ mod tests {
         assert!(list.next_token.is_none());
     }
 
+    #[sqlx::test(fixtures("quotes_likes"))]
+    async fn test_list_sort_likes(pool: PgPool) {
+        let mut app = router(pool);
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/list?sort=likes")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let list: QuoteList = serde_json::from_slice(&body).unwrap();
+
+        let likes: Vec<i32> = list.quotes.iter().map(|quote| quote.likes).collect();
+        assert_eq!(vec![10, 7, 3], likes);
+
+        let token = list.next_token.unwrap();
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri(format!("/list?token={token}"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let list: QuoteList = serde_json::from_slice(&body).unwrap();
+
+        let likes: Vec<i32> = list.quotes.iter().map(|quote| quote.likes).collect();
+        assert_eq!(vec![2, 0], likes);
+        assert!(list.next_token.is_none());
+    }
+
+    #[sqlx::test(fixtures("quotes_search"))]
+    async fn test_list_order_desc(pool: PgPool) {
+        for cursors in [CursorStore::Table, CursorStore::Signed("secret".into())] {
+            let mut app = router_with_cursors(pool.clone(), cursors);
+
+            let list = list_page(&mut app, "/list?order=desc").await;
+            let authors: Vec<&str> = list
+                .quotes
+                .iter()
+                .map(|quote| quote.author.as_str())
+                .collect();
+            assert_eq!(vec!["BOB", "GABRIEL", "BAAL"], authors);
+
+            let token = list.next_token.unwrap();
+            let list = list_page(&mut app, &format!("/list?token={token}")).await;
+            let authors: Vec<&str> = list
+                .quotes
+                .iter()
+                .map(|quote| quote.author.as_str())
+                .collect();
+            assert_eq!(vec!["LUCIFER"], authors);
+        }
+
+        let response = router(pool)
+            .oneshot(
+                Request::builder()
+                    .uri("/list?order=sideways")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(fixtures("quotes_authors"))]
+    async fn test_list_author(pool: PgPool) {
+        for cursors in [CursorStore::Table, CursorStore::Signed("secret".into())] {
+            let mut app = router_with_cursors(pool.clone(), cursors);
+
+            let list = list_page(&mut app, "/list?author=LUCIFER").await;
+            assert_eq!(list.quotes.len(), 3);
+            assert!(list.quotes.iter().all(|quote| quote.author == "LUCIFER"));
+            assert_eq!((4, 2), (list.total, list.total_pages));
+
+            let token = list.next_token.unwrap();
+            let list = list_page(&mut app, &format!("/list?token={token}")).await;
+            let quotes: Vec<&str> = list
+                .quotes
+                .iter()
+                .map(|quote| quote.quote.as_str())
+                .collect();
+            assert_eq!(quotes, vec!["Awake, arise, or be for ever fallen."]);
+            assert_eq!((2, 4, 2), (list.page, list.total, list.total_pages));
+            assert!(list.next_token.is_none());
+        }
+
+        // Matching is exact, so a prefix of an author matches nothing
+        let mut app = router(pool);
+        let list = list_page(&mut app, "/list?author=LUCI").await;
+        assert!(list.quotes.is_empty());
+        assert_eq!((0, 0), (list.total, list.total_pages));
+    }
+
+    #[sqlx::test(fixtures("quotes_authors"))]
+    async fn test_list_created_range(pool: PgPool) {
+        let days = |list: &QuoteList| -> Vec<u32> {
+            use chrono::Datelike;
+            list.quotes
+                .iter()
+                .map(|quote| quote.created_at.day())
+                .collect()
+        };
+
+        for cursors in [CursorStore::Table, CursorStore::Signed("secret".into())] {
+            let mut app = router_with_cursors(pool.clone(), cursors);
+
+            let list = list_page(&mut app, "/list?after=2024-12-03T00:00:00Z").await;
+            assert_eq!(days(&list), vec![3, 4, 5]);
+            assert_eq!((5, 2), (list.total, list.total_pages));
+            let token = list.next_token.unwrap();
+            let list = list_page(&mut app, &format!("/list?token={token}")).await;
+            assert_eq!(days(&list), vec![6, 7]);
+            assert_eq!((2, 5), (list.page, list.total));
+        }
+
+        let mut app = router(pool);
+        let list = list_page(&mut app, "/list?before=2024-12-03T00:00:00Z").await;
+        assert_eq!(days(&list), vec![1, 2]);
+        assert!(list.next_token.is_none());
+
+        let list = list_page(
+            &mut app,
+            "/list?after=2024-12-02T00:00:00Z&before=2024-12-05T12:00:00Z",
+        )
+        .await;
+        assert_eq!(days(&list), vec![2, 3, 4]);
+        assert_eq!(list.total, 4);
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/list?after=yesterday")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(fixtures("quotes_authors"))]
+    async fn test_list_page_jump(pool: PgPool) {
+        for cursors in [CursorStore::Table, CursorStore::Signed("secret".into())] {
+            let mut app = router_with_cursors(pool.clone(), cursors);
+
+            let first = list_page(&mut app, "/list").await;
+            let token = first.next_token.unwrap();
+            let sequential = list_page(&mut app, &format!("/list?token={token}")).await;
+
+            let jumped = list_page(&mut app, "/list?page=2").await;
+            assert_eq!(sequential.quotes, jumped.quotes);
+            assert_eq!(2, jumped.page);
+            assert_eq!((7, 3), (jumped.total, jumped.total_pages));
+
+            // The jumped-to page still hands out a token for the rest of the list
+            let token = jumped.next_token.unwrap();
+            let last = list_page(&mut app, &format!("/list?token={token}")).await;
+            assert_eq!(3, last.page);
+            assert_eq!(1, last.quotes.len());
+            assert!(last.next_token.is_none());
+        }
+
+        let mut app = router(pool);
+        let list = list_page(&mut app, "/list?page=4").await;
+        assert!(list.quotes.is_empty());
+        for uri in [
+            "/list?page=0",
+            "/list?page=2&token=abc",
+            "/list?page=2147483647",
+            "/list?page=100000000&limit=50",
+        ] {
+            let response = app
+                .call(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[sqlx::test(fixtures("quotes_authors"))]
+    async fn test_list_limit(pool: PgPool) {
+        for cursors in [CursorStore::Table, CursorStore::Signed("secret".into())] {
+            let mut app = router_with_cursors(pool.clone(), cursors);
+
+            let mut list = list_page(&mut app, "/list?limit=1").await;
+            assert_eq!((1, 7, 7), (list.quotes.len(), list.total, list.total_pages));
+            let mut pages = 1;
+            // Later pages keep the size from the cursor
+            while let Some(token) = list.next_token {
+                list = list_page(&mut app, &format!("/list?token={token}")).await;
+                assert_eq!(1, list.quotes.len());
+                pages += 1;
+            }
+            assert_eq!((7, 7), (pages, list.page));
+
+            let list = list_page(&mut app, "/list?limit=10").await;
+            assert_eq!((7, 1), (list.quotes.len(), list.total_pages));
+            assert!(list.next_token.is_none());
+        }
+
+        let mut app = router(pool);
+        let list = list_page(&mut app, "/list?limit=0").await;
+        assert_eq!(1, list.quotes.len());
+        let list = list_page(&mut app, "/list?limit=2&page=3").await;
+        assert_eq!((3, 2, 4), (list.page, list.quotes.len(), list.total_pages));
+    }
+
+    #[sqlx::test(fixtures("quotes_3"))]
+    async fn test_remove_quote(pool: PgPool) {
+        let mut app = router(pool);
+        let delete = || {
+            Request::builder()
+                .method("DELETE")
+                .uri("/quote/fa7a08a9-f649-4c05-917a-4b989cb25701")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.call(delete()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let response = app.call(delete()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test(fixtures("quotes_3"))]
+    async fn test_soft_delete_and_restore(pool: PgPool) {
+        let mut app = router(pool);
+        let id = "fa7a08a9-f649-4c05-917a-4b989cb25701";
+        let request = |method: &str, uri: String| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app
+            .call(request("DELETE", format!("/remove/{id}")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let list = list_page(&mut app, "/list").await;
+        assert_eq!(list.total, 2);
+        assert!(list.quotes.iter().all(|quote| quote.id.to_string() != id));
+        let response = app
+            .call(request("GET", format!("/cite/{id}")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app
+            .call(request("GET", "/trash".to_string()))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let trash: Vec<Quote> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].id.to_string(), id);
+        assert!(trash[0].deleted_at.is_some());
+
+        let response = app
+            .call(request("POST", format!("/restore/{id}")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app
+            .call(request("POST", format!("/restore/{id}")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = app
+            .call(request("GET", format!("/cite/{id}")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(list_page(&mut app, "/list").await.total, 3);
+    }
+
+    #[sqlx::test(fixtures("quotes_4"))]
+    async fn test_random(pool: PgPool) {
+        let quotes = get_quotes(&pool).await;
+        let mut app = router(pool);
+        for _ in 0..5 {
+            let response = app
+                .call(
+                    Request::builder()
+                        .uri("/random")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let quote: Quote = serde_json::from_slice(&body).unwrap();
+            assert!(quotes.contains(&quote));
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_random_empty(pool: PgPool) {
+        let response = router(pool)
+            .oneshot(
+                Request::builder()
+                    .uri("/random")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test(fixtures("quotes_3"))]
+    async fn test_remove_batch(pool: PgPool) {
+        let mut app = router(pool.clone());
+        let remove_batch = |uri: &str| {
+            let ids = [
+                "fa7a08a9-f649-4c05-917a-4b989cb25701",
+                "f7b3b1b4-3b7b-4b3b-8b3b-3b7b3b7b3b7b",
+                "00000000-0000-0000-0000-000000000000",
+            ];
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::to_vec(&ids).unwrap()))
+                .unwrap()
+        };
+
+        let response = app.call(remove_batch("/remove-batch")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let count: DeletedCount = serde_json::from_slice(&body).unwrap();
+        assert_eq!(count.deleted, 2);
+        assert_eq!(list_page(&mut app, "/list").await.total, 1);
+
+        // Already soft-deleted quotes still count when they are purged
+        let response = app
+            .call(remove_batch("/remove-batch?hard=true"))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let count: DeletedCount = serde_json::from_slice(&body).unwrap();
+        assert_eq!(count.deleted, 2);
+        assert_eq!(get_quotes(&pool).await.len(), 1);
+    }
+
+    #[sqlx::test(fixtures("quotes_3"))]
+    async fn test_hard_delete(pool: PgPool) {
+        let mut app = router(pool.clone());
+        let response = app
+            .call(
+                Request::builder()
+                    .method("DELETE")
+                    .uri("/remove/fa7a08a9-f649-4c05-917a-4b989cb25701?hard=true")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(get_quotes(&pool).await.len(), 2);
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/trash")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(serde_json::from_slice::<Vec<Quote>>(&body).unwrap(), vec![]);
+    }
+
+    #[sqlx::test(fixtures("quotes_authors"))]
+    async fn test_stream(pool: PgPool) {
+        let response = router(pool.clone())
+            .oneshot(
+                Request::builder()
+                    .uri("/stream")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/x-ndjson");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        let quotes: Vec<Quote> = body
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(quotes, get_quotes(&pool).await);
+        assert_eq!(quotes.len(), 7);
+    }
+
+    #[sqlx::test]
+    async fn test_error_envelope(pool: PgPool) {
+        let mut app = router(pool);
+        async fn error_body(app: &mut axum::Router, uri: &str) -> (StatusCode, Value) {
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            let response = app.call(request).await.unwrap();
+            let status = response.status();
+            assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            (status, serde_json::from_slice(&body).unwrap())
+        }
+
+        let uri = format!("/cite/{}", Uuid::new_v4());
+        assert_eq!(
+            error_body(&mut app, &uri).await,
+            (
+                StatusCode::NOT_FOUND,
+                json!({ "error": "not_found", "message": "quote not found" })
+            )
+        );
+        assert_eq!(
+            error_body(&mut app, "/list?page=0").await,
+            (
+                StatusCode::BAD_REQUEST,
+                json!({ "error": "bad_request", "message": "page starts at 1" })
+            )
+        );
+        let (status, body) = error_body(&mut app, "/list?token=bogus").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_token");
+
+        // Extractor rejections use the same envelope
+        let (status, body) = error_body(&mut app, "/list?order=sideways").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_query");
+        let (status, body) = error_body(&mut app, "/cite/not-a-uuid").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_path");
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/draft")
+            .header(CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+            .body(Body::from("{ not json"))
+            .unwrap();
+        let response = app.call(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"], "invalid_json");
+    }
+
+    #[sqlx::test]
+    async fn test_draft_dedupe(pool: PgPool) {
+        let mut app = router(pool);
+        async fn post_draft(app: &mut axum::Router, uri: &str) -> (StatusCode, Quote) {
+            let draft = json!({ "author": "Santa", "quote": "Ho ho ho" });
+            let request = Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header(CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .body(Body::from(draft.to_string()))
+                .unwrap();
+            let response = app.call(request).await.unwrap();
+            let status = response.status();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            (status, serde_json::from_slice(&body).unwrap())
+        }
+
+        let (status, first) = post_draft(&mut app, "/draft").await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        // Without the flag a duplicate is inserted like before
+        let (status, duplicate) = post_draft(&mut app, "/draft").await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_ne!(first.id, duplicate.id);
+
+        let (status, existing) = post_draft(&mut app, "/draft?dedupe=true").await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(existing, first);
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/count")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, r#"{"count":2}"#);
+    }
+
+    #[sqlx::test]
+    async fn test_history(pool: PgPool) {
+        let mut app = router(pool);
+        let send = |method: &str, uri: &str, body: Value| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        async fn read<T: serde::de::DeserializeOwned>(response: Response) -> T {
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            serde_json::from_slice(&body).unwrap()
+        }
+
+        let draft = json!({ "author": "Santa", "quote": "Ho ho ho" });
+        let response = app.call(send("POST", "/draft", draft)).await.unwrap();
+        let quote: Quote = read(response).await;
+        let history_uri = format!("/history/{}", quote.id);
+
+        let response = app
+            .call(send("GET", &history_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(read::<Vec<QuoteVersion>>(response).await.is_empty());
+
+        let update = json!({ "quote": "Ho ho hum" });
+        app.call(send("PATCH", &format!("/edit/{}", quote.id), update))
+            .await
+            .unwrap();
+        let update = json!({ "author": "Rudolph", "quote": "Ho hum" });
+        app.call(send("PUT", &format!("/undo/{}", quote.id), update))
+            .await
+            .unwrap();
+
+        let response = app
+            .call(send("GET", &history_uri, Value::Null))
+            .await
+            .unwrap();
+        let versions: Vec<QuoteVersion> = read(response).await;
+        let versions: Vec<_> = versions
+            .iter()
+            .map(|v| (v.version, v.author.as_str(), v.quote.as_str()))
+            .collect();
+        assert_eq!(
+            versions,
+            [(2, "Santa", "Ho ho hum"), (1, "Santa", "Ho ho ho")]
+        );
+
+        // History survives a hard delete, but unknown ids are still missing
+        let uri = format!("/remove/{}?hard=true", quote.id);
+        app.call(send("DELETE", &uri, Value::Null)).await.unwrap();
+        let response = app
+            .call(send("GET", &history_uri, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(read::<Vec<QuoteVersion>>(response).await.len(), 2);
+        let uri = format!("/history/{}", Uuid::new_v4());
+        let response = app.call(send("GET", &uri, Value::Null)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test(fixtures("quotes_4"))]
+    async fn test_concurrent_edits_keep_history(pool: PgPool) {
+        let app = router(pool.clone());
+        let id = "fa7a08a9-f649-4c05-917a-4b989cb25701";
+        let edits = (0..8).map(|n| {
+            let mut app = app.clone();
+            let (method, uri) = if n % 2 == 0 {
+                ("PATCH", format!("/edit/{id}"))
+            } else {
+                ("PUT", format!("/undo/{id}"))
+            };
+            let body = json!({ "author": "BAAL", "quote": format!("edit {n}") });
+            tokio::spawn(async move {
+                let request = Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header(CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(Body::from(body.to_string()))
+                    .unwrap();
+                app.call(request).await.unwrap().status()
+            })
+        });
+        for edit in edits.collect::<Vec<_>>() {
+            assert_eq!(edit.await.unwrap(), StatusCode::OK);
+        }
+
+        let versions = sqlx::query_scalar!(
+            "SELECT version FROM quote_versions WHERE quote_id = $1 ORDER BY version",
+            Uuid::parse_str(id).unwrap()
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(versions, (1..=8).collect::<Vec<_>>());
+    }
+
+    #[sqlx::test(fixtures("quotes_4"))]
+    async fn test_undo_deleted_quote(pool: PgPool) {
+        let mut app = router(pool.clone());
+        let id = "fa7a08a9-f649-4c05-917a-4b989cb25701";
+        let send = |method: &str, uri: String, body: Value| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        app.call(send("DELETE", format!("/remove/{id}"), Value::Null))
+            .await
+            .unwrap();
+
+        let update = json!({ "author": "BAAL", "quote": "back again" });
+        let response = app
+            .call(send("PUT", format!("/undo/{id}"), update))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let versions = sqlx::query_scalar!("SELECT COUNT(*) FROM quote_versions")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(Some(0), versions);
+    }
+
+    async fn edit_quote(app: &mut axum::Router, update: PartialQuote) -> Response {
+        app.call(
+            Request::builder()
+                .method("PATCH")
+                .uri("/edit/fa7a08a9-f649-4c05-917a-4b989cb25701")
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::to_vec(&update).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test(fixtures("quotes_3"))]
+    async fn test_edit(pool: PgPool) {
+        let mut app = router(pool);
+        let original =
+            "Go inquire of Baal-zebub, the god of Ekron, whether I shall recover from this injury.";
+
+        let update = PartialQuote {
+            author: Some("AHAZIAH".to_string()),
+            ..Default::default()
+        };
+        let response = edit_quote(&mut app, update).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let quote: Quote = serde_json::from_slice(&body).unwrap();
+        assert_eq!(quote.author, "AHAZIAH");
+        assert_eq!(quote.quote, original);
+        assert_eq!(quote.version, 2);
+
+        let update = PartialQuote {
+            quote: Some("I shall recover.".to_string()),
+            ..Default::default()
+        };
+        let response = edit_quote(&mut app, update).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let quote: Quote = serde_json::from_slice(&body).unwrap();
+        assert_eq!(quote.author, "AHAZIAH");
+        assert_eq!(quote.quote, "I shall recover.");
+        assert_eq!(quote.version, 3);
+
+        let response = edit_quote(&mut app, PartialQuote::default()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(fixtures("quotes_3"))]
+    async fn test_list_fields(pool: PgPool) {
+        let mut app = router(pool);
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/list?fields=quote")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let list: Value = serde_json::from_slice(&body).unwrap();
+
+        let quotes = list["quotes"].as_array().unwrap();
+        assert_eq!(quotes.len(), 3);
+        for quote in quotes {
+            let quote = quote.as_object().unwrap();
+            assert_eq!(quote.keys().collect::<Vec<_>>(), vec!["quote"]);
+        }
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/list?fields=quote,nope")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_quote_fields() {
+        let quote = Quote {
+            id: Uuid::new_v4(),
+            author: "Santa".to_string(),
+            quote: "Ho ho ho".to_string(),
+            created_at: chrono::Utc::now(),
+            version: 1,
+            likes: 0,
+            deleted_at: Some(chrono::Utc::now()),
+        };
+        let Value::Object(quote) = serde_json::to_value(quote).unwrap() else {
+            panic!("a quote should serialize to an object");
+        };
+        let mut keys: Vec<_> = quote.keys().map(String::as_str).collect();
+        let mut fields = QUOTE_FIELDS.to_vec();
+        keys.sort_unstable();
+        fields.sort_unstable();
+        assert_eq!(keys, fields);
+    }
+
+    #[sqlx::test(fixtures("quotes_search"))]
+    async fn test_search_ranked(pool: PgPool) {
+        let mut app = router(pool);
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/search?q=heaven&rank=true")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let list: QuoteList<SearchResult> = serde_json::from_slice(&body).unwrap();
+        let results = list.quotes;
+
+        let authors: Vec<&str> = results
+            .iter()
+            .map(|result| result.quote.author.as_str())
+            .collect();
+        assert_eq!(vec!["GABRIEL", "LUCIFER"], authors);
+        let headline = results[1].headline.as_deref().unwrap();
+        assert!(headline.contains("<b>Heaven</b>"));
+    }
+
+    #[sqlx::test(fixtures("quotes_search"))]
+    async fn test_search(pool: PgPool) {
+        let mut app = router(pool);
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/search?q=HEAVEN")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let list: QuoteList<SearchResult> = serde_json::from_slice(&body).unwrap();
+        let authors: Vec<&str> = list
+            .quotes
+            .iter()
+            .map(|result| result.quote.author.as_str())
+            .collect();
+        assert_eq!(vec!["LUCIFER", "GABRIEL"], authors);
+        assert_eq!(list.page, 1);
+        assert!(list.next_token.is_none());
+
+        for uri in ["/search?q=", "/search?q=%20%20"] {
+            let response = app
+                .call(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+    }
+
+    async fn count_quotes(pool: PgPool) -> i64 {
+        let response = router(pool)
+            .oneshot(
+                Request::builder()
+                    .uri("/count")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice::<QuoteCount>(&body).unwrap().count
+    }
+
+    #[sqlx::test(fixtures("quotes_4"))]
+    async fn test_count(pool: PgPool) {
+        assert_eq!(count_quotes(pool).await, 4);
+    }
+
+    #[sqlx::test]
+    async fn test_count_empty(pool: PgPool) {
+        assert_eq!(count_quotes(pool).await, 0);
+    }
+
+    async fn list_authors(pool: PgPool) -> Vec<String> {
+        let response = router(pool)
+            .oneshot(
+                Request::builder()
+                    .uri("/authors")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[sqlx::test(fixtures("quotes_authors"))]
+    async fn test_authors(pool: PgPool) {
+        assert_eq!(
+            list_authors(pool).await,
+            vec!["ASMODEUS", "BAAL", "LUCIFER"]
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_authors_empty(pool: PgPool) {
+        assert!(list_authors(pool).await.is_empty());
+    }
+
+    #[sqlx::test(fixtures("quotes_authors"))]
+    async fn test_export_csv(pool: PgPool) {
+        sqlx::query!(
+            "INSERT INTO quotes (id, author, quote) VALUES ($1, $2, $3)",
+            Uuid::new_v4(),
+            "MAMMON",
+            r#"Gold, and "more" gold"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let quotes = get_quotes(&pool).await;
+
+        let response = router(pool)
+            .oneshot(
+                Request::builder()
+                    .uri("/export.csv")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/csv");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let raw = String::from_utf8(body.to_vec()).unwrap();
+        assert!(raw.contains(r#",MAMMON,"Gold, and ""more"" gold","#));
+
+        let mut reader = csv::Reader::from_reader(body.as_ref());
+        assert_eq!(
+            reader.headers().unwrap(),
+            vec!["id", "author", "quote", "created_at", "version"]
+        );
+        let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), quotes.len());
+        for (row, quote) in rows.iter().zip(&quotes) {
+            assert_eq!(row[0], quote.id.to_string());
+            assert_eq!(row[1], quote.author);
+            assert_eq!(row[2], quote.quote);
+            assert_eq!(
+                row[3].parse::<chrono::DateTime<chrono::Utc>>().unwrap(),
+                quote.created_at
+            );
+            assert_eq!(row[4], quote.version.to_string());
+        }
+        assert_eq!(&rows.last().unwrap()[2], r#"Gold, and "more" gold"#);
+    }
+
+    async fn list_page(app: &mut axum::Router, uri: &str) -> QuoteList {
+        let response = app
+            .call(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[sqlx::test(fixtures("quotes_4"))]
+    async fn test_list_signed_token(pool: PgPool) {
+        let quotes = get_quotes(&pool).await;
+        let mut app = router_with_cursors(pool.clone(), CursorStore::Signed("secret".into()));
+
+        let list = list_page(&mut app, "/list").await;
+        assert_eq!(&quotes[..3], &list.quotes);
+        let token = list.next_token.unwrap();
+
+        let list = list_page(&mut app, &format!("/list?token={token}")).await;
+        assert_eq!(&quotes[3..], &list.quotes);
+        assert_eq!(2, list.page);
+        assert_eq!(None, list.next_token);
+
+        // No cursor rows are needed for signed tokens
+        let cursors = sqlx::query_scalar!("SELECT COUNT(*) FROM cursors")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(Some(0), cursors);
+    }
+
+    #[sqlx::test(fixtures("quotes_4"))]
+    async fn test_list_legacy_signed_token(pool: PgPool) {
+        let quotes = get_quotes(&pool).await;
+        let mut app = router_with_cursors(pool, CursorStore::Signed("secret".into()));
+
+        // Signed before tokens carried a direction, page size or epoch
+        let claims = json!({
+            "page": 1,
+            "created_at": chrono::Utc::now(),
+            "sort": "created_at",
+            "author": null,
+            "created_after": null,
+            "created_before": null,
+        });
+        let token = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &claims,
+            &jsonwebtoken::EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+
+        let list = list_page(&mut app, &format!("/list?token={token}")).await;
+        assert_eq!(&quotes[3..], &list.quotes);
+        assert_eq!(2, list.page);
+    }
+
+    #[sqlx::test(fixtures("quotes_4"))]
+    async fn test_list_tampered_token(pool: PgPool) {
+        let mut app = router_with_cursors(pool, CursorStore::Signed("secret".into()));
+
+        let list = list_page(&mut app, "/list").await;
+        let token = list.next_token.unwrap();
+
+        // Swap the signed payload for one claiming a different page
+        let (header, rest) = token.split_once('.').unwrap();
+        let (_, signature) = rest.split_once('.').unwrap();
+        let forged = format!("{header}.eyJwYWdlIjo1fQ.{signature}");
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri(format!("/list?token={forged}"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(fixtures("quotes_4"))]
+    async fn test_reset_invalidates_tokens(pool: PgPool) {
+        for cursors in [CursorStore::Table, CursorStore::Signed("secret".into())] {
+            let mut app = router_with_cursors(pool.clone(), cursors);
+            let list = list_page(&mut app, "/list").await;
+            let token = list.next_token.unwrap();
+
+            let response = app
+                .call(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/reset")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let response = app
+                .call(
+                    Request::builder()
+                        .uri(format!("/list?token={token}"))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+            sqlx::raw_sql(include_str!("fixtures/quotes_4.sql"))
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[sqlx::test(fixtures("quotes_4"))]
+    async fn test_list_table_token(pool: PgPool) {
+        let quotes = get_quotes(&pool).await;
+        let mut app = router_with_cursors(pool, CursorStore::Table);
+
+        let list = list_page(&mut app, "/list").await;
+        let token = list.next_token.unwrap();
+
+        let list = list_page(&mut app, &format!("/list?token={token}")).await;
+        assert_eq!(&quotes[3..], &list.quotes);
+        assert_eq!(None, list.next_token);
+    }
+
     async fn get_quotes(pool: &PgPool) -> Vec<Quote> {
         sqlx::query_as!(Quote, "SELECT * FROM quotes ORDER BY created_at ASC")
             .fetch_all(pool)
@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::error;
+
+use super::{error::ApiError, ListOrder, DEFAULT_PAGE_SIZE};
+
+#[derive(Deserialize, Serialize)]
+pub struct Cursor {
+    #[serde(skip)]
+    pub token: String,
+    pub page: i32,
+    pub created_at: DateTime<Utc>,
+    pub sort: String,
+    // Tokens signed before descending lists don't carry a direction
+    #[serde(default = "default_direction")]
+    pub direction: String,
+    pub author: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    // Tokens signed before page sizes were configurable don't carry one
+    #[serde(default = "default_page_size")]
+    pub page_size: i32,
+}
+
+fn default_page_size() -> i32 {
+    DEFAULT_PAGE_SIZE
+}
+
+fn default_direction() -> String {
+    ListOrder::Asc.as_str().to_string()
+}
+
+// Signed tokens are stamped with the epoch they were issued in, bumping the epoch on
+// reset stops every token handed out before it from verifying
+#[derive(Deserialize)]
+struct SignedCursor {
+    #[serde(flatten)]
+    cursor: Cursor,
+    // Tokens signed before epochs existed count as the first one
+    #[serde(default)]
+    epoch: i64,
+}
+
+// Signed cursors carry their own state in an HMAC-signed token, table cursors
+// are opaque random tokens pointing at a row in the `cursors` table.
+#[derive(Clone)]
+pub enum CursorStore {
+    Table,
+    Signed(Arc<str>),
+}
+
+impl CursorStore {
+    // Signing needs a secret shared by every instance and kept across restarts,
+    // without one the table store is the only one whose tokens stay valid
+    pub fn from_env() -> Self {
+        if std::env::var("QUOTE_CURSORS").is_ok_and(|mode| mode == "table") {
+            return CursorStore::Table;
+        }
+        match std::env::var("QUOTE_CURSOR_SECRET") {
+            Ok(secret) if !secret.is_empty() => CursorStore::Signed(secret.into()),
+            _ => CursorStore::Table,
+        }
+    }
+
+    pub async fn create(&self, pool: &PgPool, cursor: &Cursor) -> Result<String, ApiError> {
+        match self {
+            CursorStore::Table => {
+                sqlx::query!(
+                    "INSERT INTO cursors
+                       (token, page, created_at, sort, direction, author,
+                        created_after, created_before, page_size)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+                    cursor.token,
+                    cursor.page,
+                    cursor.created_at,
+                    cursor.sort,
+                    cursor.direction,
+                    cursor.author,
+                    cursor.created_after,
+                    cursor.created_before,
+                    cursor.page_size,
+                )
+                .execute(pool)
+                .await?;
+                Ok(cursor.token.clone())
+            }
+            CursorStore::Signed(secret) => sign(secret, cursor, current_epoch(pool).await?),
+        }
+    }
+
+    pub async fn load(&self, pool: &PgPool, token: &str) -> Result<Cursor, ApiError> {
+        match self {
+            CursorStore::Table => sqlx::query_as!(
+                Cursor,
+                "SELECT token, page, created_at, sort, direction, author,
+                        created_after, created_before, page_size
+                 FROM cursors WHERE token = $1",
+                token
+            )
+            .fetch_optional(pool)
+            .await?
+            .ok_or(ApiError::InvalidToken),
+            CursorStore::Signed(secret) => {
+                let signed = verify(secret, token)?;
+                if signed.epoch != current_epoch(pool).await? {
+                    return Err(ApiError::InvalidToken);
+                }
+                Ok(signed.cursor)
+            }
+        }
+    }
+
+    pub async fn advance(&self, pool: &PgPool, cursor: Cursor) -> Result<String, ApiError> {
+        let cursor = Cursor {
+            page: cursor.page + 1,
+            ..cursor
+        };
+        match self {
+            CursorStore::Table => {
+                sqlx::query!(
+                    "UPDATE cursors SET page = $1 WHERE token = $2",
+                    cursor.page,
+                    cursor.token
+                )
+                .execute(pool)
+                .await?;
+                Ok(cursor.token)
+            }
+            CursorStore::Signed(secret) => sign(secret, &cursor, current_epoch(pool).await?),
+        }
+    }
+
+    pub async fn finish(&self, pool: &PgPool, cursor: Cursor) -> Result<(), ApiError> {
+        match self {
+            CursorStore::Table => {
+                sqlx::query!("DELETE FROM cursors WHERE token = $1", cursor.token)
+                    .execute(pool)
+                    .await?;
+                Ok(())
+            }
+            // Nothing is stored server side, the token simply stops being handed out
+            CursorStore::Signed(_) => Ok(()),
+        }
+    }
+
+    // Drops every outstanding cursor, whichever store issued it
+    pub async fn clear(pool: &PgPool) -> Result<(), ApiError> {
+        sqlx::query!("DELETE FROM cursors").execute(pool).await?;
+        sqlx::query!(
+            "INSERT INTO cursor_epoch (id, epoch) VALUES (1, 1)
+             ON CONFLICT (id) DO UPDATE SET epoch = cursor_epoch.epoch + 1"
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
+
+async fn current_epoch(pool: &PgPool) -> Result<i64, ApiError> {
+    let epoch = sqlx::query_scalar!("SELECT epoch FROM cursor_epoch WHERE id = 1")
+        .fetch_optional(pool)
+        .await?;
+    Ok(epoch.unwrap_or_default())
+}
+
+fn sign(secret: &str, cursor: &Cursor, epoch: i64) -> Result<String, ApiError> {
+    // The claims only borrow the cursor's fields, so serialize through a reference
+    #[derive(Serialize)]
+    struct Claims<'a> {
+        #[serde(flatten)]
+        cursor: &'a Cursor,
+        epoch: i64,
+    }
+    jsonwebtoken::encode(
+        &Header::default(),
+        &Claims { cursor, epoch },
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .inspect_err(|err| error!(%err, "failed to sign cursor"))
+    .map_err(|_| ApiError::Internal)
+}
+
+fn verify(secret: &str, token: &str) -> Result<SignedCursor, ApiError> {
+    let mut validation = Validation::default();
+    validation.required_spec_claims = Default::default();
+    validation.validate_exp = false;
+    jsonwebtoken::decode::<SignedCursor>(
+        token,
+        &DecodingKey::from_secret(secret.as_bytes()),
+        &validation,
+    )
+    .map(|data| data.claims)
+    .inspect_err(|err| error!(%err, "cursor token was rejected"))
+    .map_err(|_| ApiError::InvalidToken)
+}
@@ -0,0 +1,76 @@
+use axum::{
+    extract::rejection::{JsonRejection, PathRejection, QueryRejection},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use thiserror::Error;
+use tracing::error;
+
+#[derive(Error, Debug)]
+pub enum ApiError {
+    #[error("quote not found")]
+    NotFound,
+    #[error("{0}")]
+    BadRequest(&'static str),
+    #[error("list token is not valid")]
+    InvalidToken,
+    #[error("{0}")]
+    InvalidJson(#[from] JsonRejection),
+    #[error("{0}")]
+    InvalidQuery(#[from] QueryRejection),
+    #[error("{0}")]
+    InvalidPath(#[from] PathRejection),
+    #[error("database request failed")]
+    Database(sqlx::Error),
+    #[error("internal error")]
+    Internal,
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(error: sqlx::Error) -> Self {
+        match error {
+            sqlx::Error::RowNotFound => ApiError::NotFound,
+            error => ApiError::Database(error),
+        }
+    }
+}
+
+impl ApiError {
+    fn status(&self) -> StatusCode {
+        match self {
+            ApiError::NotFound => StatusCode::NOT_FOUND,
+            ApiError::BadRequest(_) | ApiError::InvalidToken => StatusCode::BAD_REQUEST,
+            // Keeps the rejection's own status, e.g. 415 for a missing content type
+            ApiError::InvalidJson(rejection) => rejection.status(),
+            ApiError::InvalidQuery(rejection) => rejection.status(),
+            ApiError::InvalidPath(rejection) => rejection.status(),
+            ApiError::Database(_) | ApiError::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            ApiError::NotFound => "not_found",
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::InvalidToken => "invalid_token",
+            ApiError::InvalidJson(_) => "invalid_json",
+            ApiError::InvalidQuery(_) => "invalid_query",
+            ApiError::InvalidPath(_) => "invalid_path",
+            ApiError::Database(_) => "database_error",
+            ApiError::Internal => "internal_error",
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        // The database error itself is only logged, clients get the generic message
+        if let ApiError::Database(error) = &self {
+            error!(%error, "quote query failed");
+        }
+        let body = json!({ "error": self.code(), "message": self.to_string() });
+        (self.status(), Json(body)).into_response()
+    }
+}
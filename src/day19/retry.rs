@@ -0,0 +1,93 @@
+use std::{future::Future, time::Duration};
+
+use tracing::warn;
+
+const MAX_RETRIES: u32 = 3;
+const BASE_DELAY: Duration = Duration::from_millis(50);
+
+// Only errors from losing the connection are worth another attempt, anything the
+// database actually answered (missing rows, constraint violations) would just fail again
+fn is_transient(error: &sqlx::Error) -> bool {
+    matches!(
+        error,
+        sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut | sqlx::Error::WorkerCrashed
+    )
+}
+
+// Runs an idempotent read, retrying with exponential backoff on connection errors
+pub async fn with_retry<T, F, Fut>(mut query: F) -> Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let mut attempt = 0;
+    loop {
+        match query().await {
+            Err(error) if attempt < MAX_RETRIES && is_transient(&error) => {
+                let delay = BASE_DELAY * 2u32.pow(attempt);
+                warn!(%error, attempt, ?delay, "retrying database read");
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    use sqlx::PgPool;
+
+    fn disconnected() -> sqlx::Error {
+        sqlx::Error::Io(io::Error::from(io::ErrorKind::ConnectionReset))
+    }
+
+    #[sqlx::test]
+    async fn test_retry_after_disconnect(pool: PgPool) {
+        let mut calls = 0;
+        let value = with_retry(|| {
+            calls += 1;
+            let first = calls == 1;
+            let pool = pool.clone();
+            async move {
+                if first {
+                    return Err(disconnected());
+                }
+                sqlx::query_scalar!(r#"SELECT 42 AS "value!""#)
+                    .fetch_one(&pool)
+                    .await
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!((value, calls), (42, 2));
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up() {
+        let mut calls = 0;
+        let result: Result<(), _> = with_retry(|| {
+            calls += 1;
+            async { Err(disconnected()) }
+        })
+        .await;
+        assert!(matches!(result, Err(sqlx::Error::Io(_))));
+        assert_eq!(calls, MAX_RETRIES + 1);
+    }
+
+    #[tokio::test]
+    async fn test_no_retry_on_missing_row() {
+        let mut calls = 0;
+        let result: Result<(), _> = with_retry(|| {
+            calls += 1;
+            async { Err(sqlx::Error::RowNotFound) }
+        })
+        .await;
+        assert!(matches!(result, Err(sqlx::Error::RowNotFound)));
+        assert_eq!(calls, 1);
+    }
+}
@@ -1,25 +1,192 @@
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::{
+    fmt::Display,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
 
-use axum::extract::Query;
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{rejection::JsonRejection, FromRequest, FromRequestParts, Request},
+    http::{header, request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use serde_path_to_error::Segment;
+use utoipa::{OpenApi, ToSchema};
 
-#[derive(serde::Deserialize)]
+#[derive(OpenApi)]
+#[openapi(paths(
+    dest,
+    dest_batch,
+    key,
+    key_network,
+    subnet,
+    mid,
+    dest_v6,
+    key_v6,
+    mid_v6,
+    dest_auto
+))]
+pub struct ApiDoc;
+
+// Addresses get their own message, other fields are only named
+const ADDRESS_FIELDS: &[&str] = &["from", "key", "to", "addr"];
+
+#[derive(Debug, PartialEq)]
+pub struct ParamsRejection {
+    field: Option<String>,
+    from_body: bool,
+}
+
+impl IntoResponse for ParamsRejection {
+    fn into_response(self) -> Response {
+        let error = match self.field.as_deref() {
+            Some(field) if ADDRESS_FIELDS.contains(&field) => "invalid address",
+            Some(_) => "invalid value",
+            None if self.from_body => "invalid body",
+            None => "invalid query",
+        };
+        let body = json!({ "error": error, "field": self.field });
+        (StatusCode::BAD_REQUEST, Json(body)).into_response()
+    }
+}
+
+// Like Query, but a failed field is reported back as JSON instead of axum's plain text
+pub struct AddrQuery<T>(pub T);
+
+#[async_trait]
+impl<T: DeserializeOwned, S: Send + Sync> FromRequestParts<S> for AddrQuery<T> {
+    type Rejection = ParamsRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        query_params(parts.uri.query().unwrap_or_default()).map(AddrQuery)
+    }
+}
+
+fn query_params<T: DeserializeOwned>(query: &str) -> Result<T, ParamsRejection> {
+    let deserializer =
+        serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+    serde_path_to_error::deserialize(deserializer).map_err(|error| ParamsRejection {
+        field: failed_field(error.path()),
+        from_body: false,
+    })
+}
+
+fn json_params<T: DeserializeOwned>(body: &[u8]) -> Result<T, ParamsRejection> {
+    let mut deserializer = serde_json::Deserializer::from_slice(body);
+    serde_path_to_error::deserialize(&mut deserializer).map_err(|error| ParamsRejection {
+        field: failed_field(error.path()),
+        from_body: true,
+    })
+}
+
+// Missing fields and syntax errors fail at the root, so there is no field to point at
+fn failed_field(path: &serde_path_to_error::Path) -> Option<String> {
+    path.iter().find_map(|segment| match segment {
+        Segment::Map { key } => Some(key.clone()),
+        _ => None,
+    })
+}
+
+#[derive(serde::Deserialize, ToSchema)]
 pub struct DestParams {
+    #[schema(value_type = String)]
     from: Ipv4Addr,
+    #[schema(value_type = String)]
     key: Ipv4Addr,
 }
 
-pub async fn dest(Query(params): Query<DestParams>) -> String {
-    let from_octets = params.from.octets();
-    let key_octets = params.key.octets();
-    let to: Ipv4Addr = [
-        from_octets[0].wrapping_add(key_octets[0]),
-        from_octets[1].wrapping_add(key_octets[1]),
-        from_octets[2].wrapping_add(key_octets[2]),
-        from_octets[3].wrapping_add(key_octets[3]),
+#[derive(serde::Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OctetMode {
+    #[default]
+    Wrap,
+    Saturate,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ModeParams {
+    #[serde(default)]
+    mode: OctetMode,
+}
+
+#[utoipa::path(
+    method(get, post),
+    path = "/dest",
+    params(
+        ("from" = String, Query, description = "IPv4 address"),
+        ("key" = String, Query, description = "IPv4 address"),
+        ("mode" = Option<String>, Query, description = "wrap (default) or saturate"),
+    ),
+    request_body(content = Option<DestParams>, description = "Used instead of the query for application/json requests"),
+    responses(
+        (status = OK, body = String),
+        (status = BAD_REQUEST),
+    ),
+)]
+pub async fn dest(request: Request) -> Result<String, Response> {
+    // The mode always comes from the query string, even for JSON bodies
+    let query = request.uri().query().unwrap_or_default();
+    let ModeParams { mode } = query_params(query).map_err(IntoResponse::into_response)?;
+    // Parameters like charset don't change the format, and media types are case-insensitive
+    let is_json = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(|content_type| content_type.parse::<mime::Mime>().ok())
+        .is_some_and(|mime| mime.essence_str().eq_ignore_ascii_case("application/json"));
+    let params: DestParams = if is_json {
+        let body = Bytes::from_request(request, &())
+            .await
+            .map_err(IntoResponse::into_response)?;
+        json_params(&body).map_err(IntoResponse::into_response)?
+    } else {
+        let query = request.uri().query().unwrap_or_default();
+        query_params(query).map_err(IntoResponse::into_response)?
+    };
+
+    Ok(apply_key(params.from, params.key, mode).to_string())
+}
+
+#[utoipa::path(
+    post,
+    path = "/dest/batch",
+    request_body = Vec<DestParams>,
+    responses(
+        (status = OK, body = Vec<String>),
+        (status = BAD_REQUEST),
+    ),
+)]
+pub async fn dest_batch(
+    body: Result<Json<Vec<DestParams>>, JsonRejection>,
+) -> Result<Json<Vec<String>>, StatusCode> {
+    let Json(batch) = body.map_err(|_| StatusCode::BAD_REQUEST)?;
+    let destinations = batch
+        .into_iter()
+        .map(|params| apply_key(params.from, params.key, OctetMode::Wrap).to_string())
+        .collect();
+    Ok(Json(destinations))
+}
+
+fn apply_key(from: Ipv4Addr, key: Ipv4Addr, mode: OctetMode) -> Ipv4Addr {
+    let from_octets = from.octets();
+    let key_octets = key.octets();
+    [
+        apply_octet(from_octets[0], key_octets[0], mode),
+        apply_octet(from_octets[1], key_octets[1], mode),
+        apply_octet(from_octets[2], key_octets[2], mode),
+        apply_octet(from_octets[3], key_octets[3], mode),
     ]
-    .into();
+    .into()
+}
 
-    to.to_string()
+fn apply_octet(a: u8, b: u8, mode: OctetMode) -> u8 {
+    match mode {
+        OctetMode::Wrap => a.wrapping_add(b),
+        OctetMode::Saturate => a.saturating_add(b),
+    }
 }
 
 #[derive(serde::Deserialize)]
@@ -28,18 +195,131 @@ pub struct KeyParams {
     to: Ipv4Addr,
 }
 
-pub async fn key(Query(params): Query<KeyParams>) -> String {
-    let from_octets = params.from.octets();
-    let to_octets = params.to.octets();
-    let key: Ipv4Addr = [
+#[utoipa::path(
+    get,
+    path = "/key",
+    params(
+        ("from" = String, Query),
+        ("to" = String, Query),
+    ),
+    responses((status = OK, body = String)),
+)]
+pub async fn key(AddrQuery(params): AddrQuery<KeyParams>) -> String {
+    ipv4_key(params.from, params.to).to_string()
+}
+
+#[derive(serde::Deserialize)]
+pub struct NetworkKeyParams {
+    from: Ipv4Addr,
+    to: Ipv4Addr,
+    prefix: u8,
+}
+
+#[utoipa::path(
+    get,
+    path = "/key/network",
+    params(
+        ("from" = String, Query),
+        ("to" = String, Query),
+        ("prefix" = u8, Query),
+    ),
+    responses(
+        (status = OK, body = String),
+        (status = BAD_REQUEST),
+    ),
+)]
+pub async fn key_network(
+    AddrQuery(params): AddrQuery<NetworkKeyParams>,
+) -> Result<String, StatusCode> {
+    if params.prefix > 32 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let key = u32::from(ipv4_key(params.from, params.to));
+    // Only the host portion of the key is meaningful within the subnet
+    let host_mask = u32::MAX.checked_shr(params.prefix.into()).unwrap_or(0);
+    let key: Ipv4Addr = (key & host_mask).into();
+
+    Ok(key.to_string())
+}
+
+#[derive(serde::Deserialize)]
+pub struct SubnetParams {
+    addr: Ipv4Addr,
+    prefix: u8,
+}
+
+#[derive(serde::Serialize, ToSchema, Debug, PartialEq)]
+pub struct Subnet {
+    #[schema(value_type = String)]
+    network: Ipv4Addr,
+    #[schema(value_type = String)]
+    broadcast: Ipv4Addr,
+    hosts: u32,
+}
+
+#[utoipa::path(
+    get,
+    path = "/subnet",
+    params(("addr" = String, Query), ("prefix" = u8, Query)),
+    responses((status = OK, body = Subnet), (status = BAD_REQUEST)),
+)]
+pub async fn subnet(
+    AddrQuery(params): AddrQuery<SubnetParams>,
+) -> Result<Json<Subnet>, StatusCode> {
+    if params.prefix > 32 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let addr = u32::from(params.addr);
+    let host_mask = u32::MAX.checked_shr(params.prefix.into()).unwrap_or(0);
+    let network = addr & !host_mask;
+    let broadcast = network | host_mask;
+    // /31 and /32 have no network or broadcast address to set aside (RFC 3021)
+    let hosts = match params.prefix {
+        31 => 2,
+        32 => 1,
+        _ => host_mask - 1,
+    };
+
+    Ok(Json(Subnet {
+        network: network.into(),
+        broadcast: broadcast.into(),
+        hosts,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/mid",
+    params(
+        ("from" = String, Query),
+        ("to" = String, Query),
+    ),
+    responses(
+        (status = OK, body = String),
+        (status = BAD_REQUEST),
+    ),
+)]
+pub async fn mid(AddrQuery(params): AddrQuery<KeyParams>) -> String {
+    let mid = midpoint(u32::from(params.from).into(), u32::from(params.to).into());
+    Ipv4Addr::from(mid as u32).to_string()
+}
+
+// Floor of the average, computed from the lower bound so u128 addresses can't overflow
+fn midpoint(a: u128, b: u128) -> u128 {
+    let (low, high) = (a.min(b), a.max(b));
+    low + (high - low) / 2
+}
+
+fn ipv4_key(from: Ipv4Addr, to: Ipv4Addr) -> Ipv4Addr {
+    let from_octets = from.octets();
+    let to_octets = to.octets();
+    [
         to_octets[0].wrapping_sub(from_octets[0]),
         to_octets[1].wrapping_sub(from_octets[1]),
         to_octets[2].wrapping_sub(from_octets[2]),
         to_octets[3].wrapping_sub(from_octets[3]),
     ]
-    .into();
-
-    key.to_string()
+    .into()
 }
 
 #[derive(serde::Deserialize)]
@@ -48,8 +328,17 @@ pub struct DestV6Params {
     key: Ipv6Addr,
 }
 
-pub async fn dest_v6(Query(params): Query<DestV6Params>) -> String {
-    ipv6_xor(params.from, params.key).to_string()
+#[utoipa::path(
+    get,
+    path = "/v6/dest",
+    params(
+        ("from" = String, Query),
+        ("key" = String, Query),
+    ),
+    responses((status = OK, body = String)),
+)]
+pub async fn dest_v6(AddrQuery(params): AddrQuery<DestV6Params>) -> String {
+    ipv6_dest(params.from, params.key).to_string()
 }
 
 #[derive(serde::Deserialize)]
@@ -58,8 +347,42 @@ pub struct KeyV6Params {
     to: Ipv6Addr,
 }
 
-pub async fn key_v6(Query(params): Query<KeyV6Params>) -> String {
-    ipv6_xor(params.from, params.to).to_string()
+#[utoipa::path(
+    get,
+    path = "/v6/key",
+    params(
+        ("from" = String, Query),
+        ("to" = String, Query),
+    ),
+    responses((status = OK, body = String)),
+)]
+pub async fn key_v6(AddrQuery(params): AddrQuery<KeyV6Params>) -> String {
+    ipv6_key(params.from, params.to).to_string()
+}
+
+#[utoipa::path(
+    get,
+    path = "/v6/mid",
+    params(
+        ("from" = String, Query),
+        ("to" = String, Query),
+    ),
+    responses(
+        (status = OK, body = String),
+        (status = BAD_REQUEST),
+    ),
+)]
+pub async fn mid_v6(AddrQuery(params): AddrQuery<KeyV6Params>) -> String {
+    Ipv6Addr::from(midpoint(params.from.into(), params.to.into())).to_string()
+}
+
+// XOR is its own inverse, so applying a key and recovering it share one primitive
+fn ipv6_dest(from: Ipv6Addr, key: Ipv6Addr) -> Ipv6Addr {
+    ipv6_xor(from, key)
+}
+
+fn ipv6_key(from: Ipv6Addr, to: Ipv6Addr) -> Ipv6Addr {
+    ipv6_xor(from, to)
 }
 
 fn ipv6_xor(first: Ipv6Addr, second: Ipv6Addr) -> Ipv6Addr {
@@ -75,3 +398,412 @@ fn ipv6_xor(first: Ipv6Addr, second: Ipv6Addr) -> Ipv6Addr {
 
     result_octets.into()
 }
+
+#[derive(serde::Deserialize)]
+pub struct AutoDestParams {
+    from: IpAddr,
+    key: IpAddr,
+}
+
+enum AddrKind {
+    V4,
+    V6,
+}
+
+impl From<IpAddr> for AddrKind {
+    fn from(addr: IpAddr) -> Self {
+        match addr {
+            IpAddr::V4(_) => AddrKind::V4,
+            IpAddr::V6(_) => AddrKind::V6,
+        }
+    }
+}
+
+impl Display for AddrKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddrKind::V4 => write!(f, "IPv4"),
+            AddrKind::V6 => write!(f, "IPv6"),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/auto/dest",
+    params(
+        ("from" = String, Query),
+        ("key" = String, Query),
+    ),
+    responses(
+        (status = OK, body = String),
+        (status = BAD_REQUEST, body = String),
+    ),
+)]
+pub async fn dest_auto(
+    AddrQuery(params): AddrQuery<AutoDestParams>,
+) -> Result<String, (StatusCode, String)> {
+    match (params.from, params.key) {
+        (IpAddr::V4(from), IpAddr::V4(key)) => {
+            Ok(apply_key(from, key, OctetMode::Wrap).to_string())
+        }
+        (IpAddr::V6(from), IpAddr::V6(key)) => Ok(ipv6_dest(from, key).to_string()),
+        (from, key) => Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "from is {} but key is {}",
+                AddrKind::from(from),
+                AddrKind::from(key)
+            ),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{body::Body, routing::post, Router};
+    use http_body_util::BodyExt;
+    use rand::Rng;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_dest_json() {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/2/dest")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"from":"1.2.3.4","key":"5.6.7.8"}"#))
+            .unwrap();
+        let from_json = dest(request).await.unwrap();
+
+        let request = Request::builder()
+            .uri("/2/dest?from=1.2.3.4&key=5.6.7.8")
+            .body(Body::empty())
+            .unwrap();
+        let from_query = dest(request).await.unwrap();
+
+        assert_eq!(from_json, "6.8.10.12");
+        assert_eq!(from_json, from_query);
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/2/dest")
+            .header(header::CONTENT_TYPE, "Application/JSON; charset=utf-8")
+            .body(Body::from(r#"{"from":"1.2.3.4","key":"5.6.7.8"}"#))
+            .unwrap();
+        assert_eq!(dest(request).await.unwrap(), "6.8.10.12");
+    }
+
+    #[tokio::test]
+    async fn test_dest_json_rejection() {
+        async fn rejection(body: &'static str) -> (StatusCode, serde_json::Value) {
+            let request = Request::builder()
+                .method("POST")
+                .uri("/2/dest")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+                .unwrap();
+            let response = dest(request).await.unwrap_err();
+            let status = response.status();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            (status, serde_json::from_slice(&body).unwrap())
+        }
+
+        assert_eq!(
+            rejection(r#"{"from":"1.2.3","key":"5.6.7.8"}"#).await,
+            (
+                StatusCode::BAD_REQUEST,
+                json!({ "error": "invalid address", "field": "from" })
+            )
+        );
+        assert_eq!(
+            rejection(r#"{"from":"1.2.3.4","key":17}"#).await,
+            (
+                StatusCode::BAD_REQUEST,
+                json!({ "error": "invalid address", "field": "key" })
+            )
+        );
+        assert_eq!(
+            rejection(r#"{"from":"1.2.3.4""#).await,
+            (
+                StatusCode::BAD_REQUEST,
+                json!({ "error": "invalid body", "field": null })
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dest_saturate() {
+        assert_eq!(apply_octet(250, 10, OctetMode::Wrap), 4);
+        assert_eq!(apply_octet(250, 10, OctetMode::Saturate), 255);
+        assert_eq!(apply_octet(100, 10, OctetMode::Saturate), 110);
+
+        let dest_uri = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let wrapped = dest(dest_uri("/2/dest?from=250.1.2.3&key=10.0.0.0"))
+            .await
+            .unwrap();
+        assert_eq!(wrapped, "4.1.2.3");
+        let saturated = dest(dest_uri(
+            "/2/dest?from=250.1.2.3&key=10.0.0.0&mode=saturate",
+        ))
+        .await
+        .unwrap();
+        assert_eq!(saturated, "255.1.2.3");
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/2/dest?mode=saturate")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"from":"250.1.2.3","key":"10.0.0.0"}"#))
+            .unwrap();
+        assert_eq!(dest(request).await.unwrap(), "255.1.2.3");
+
+        let response = dest(dest_uri("/2/dest?from=1.2.3.4&key=1.2.3.4&mode=clamp"))
+            .await
+            .unwrap_err();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_dest_batch() {
+        let app = Router::new().route("/", post(dest_batch));
+        let batch = |body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .uri("/")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(batch(
+                r#"[
+                    {"from": "10.0.0.0", "key": "1.2.3.255"},
+                    {"from": "128.128.33.0", "key": "255.0.255.33"},
+                    {"from": "192.168.0.1", "key": "0.0.0.0"}
+                ]"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let destinations: Vec<String> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            destinations,
+            vec!["11.2.3.255", "127.128.32.33", "192.168.0.1"]
+        );
+
+        let response = app
+            .oneshot(batch(
+                r#"[
+                    {"from": "10.0.0.0", "key": "1.2.3.255"},
+                    {"from": "128.128.33.0", "key": "255.0.255.33"},
+                    {"from": "192.168.0.1", "key": "0.0.0.0"},
+                    {"from": "not an address", "key": "0.0.0.0"}
+                ]"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_ipv6_round_trip() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..32 {
+            let from = Ipv6Addr::from(rng.gen::<u128>());
+            let key = Ipv6Addr::from(rng.gen::<u128>());
+            assert_eq!(ipv6_key(from, ipv6_dest(from, key)), key);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dest_auto() {
+        let params = AutoDestParams {
+            from: "10.0.0.0".parse().unwrap(),
+            key: "1.2.3.255".parse().unwrap(),
+        };
+        assert_eq!(dest_auto(AddrQuery(params)).await.unwrap(), "11.2.3.255");
+
+        let params = AutoDestParams {
+            from: "fe80::1".parse().unwrap(),
+            key: "5:6:7::3333".parse().unwrap(),
+        };
+        assert_eq!(
+            dest_auto(AddrQuery(params)).await.unwrap(),
+            "fe85:6:7::3332"
+        );
+
+        let params = AutoDestParams {
+            from: "10.0.0.0".parse().unwrap(),
+            key: "5:6:7::3333".parse().unwrap(),
+        };
+        let (status, message) = dest_auto(AddrQuery(params)).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(message, "from is IPv4 but key is IPv6");
+    }
+
+    #[tokio::test]
+    async fn test_key_network() {
+        let params = NetworkKeyParams {
+            from: Ipv4Addr::new(10, 0, 0, 5),
+            to: Ipv4Addr::new(10, 0, 0, 42),
+            prefix: 24,
+        };
+        let key: Ipv4Addr = key_network(AddrQuery(params))
+            .await
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(key.octets(), [0, 0, 0, 37]);
+    }
+
+    #[tokio::test]
+    async fn test_key_network_invalid_prefix() {
+        let params = NetworkKeyParams {
+            from: Ipv4Addr::new(10, 0, 0, 5),
+            to: Ipv4Addr::new(10, 0, 0, 42),
+            prefix: 33,
+        };
+        let actual = key_network(AddrQuery(params)).await;
+        assert_eq!(actual, Err(StatusCode::BAD_REQUEST));
+    }
+
+    async fn subnet_of(addr: [u8; 4], prefix: u8) -> Result<Subnet, StatusCode> {
+        let params = SubnetParams {
+            addr: addr.into(),
+            prefix,
+        };
+        subnet(AddrQuery(params)).await.map(|Json(subnet)| subnet)
+    }
+
+    #[tokio::test]
+    async fn test_subnet() {
+        assert_eq!(
+            subnet_of([192, 168, 1, 77], 24).await,
+            Ok(Subnet {
+                network: Ipv4Addr::new(192, 168, 1, 0),
+                broadcast: Ipv4Addr::new(192, 168, 1, 255),
+                hosts: 254,
+            })
+        );
+        assert_eq!(
+            subnet_of([10, 0, 0, 7], 31).await,
+            Ok(Subnet {
+                network: Ipv4Addr::new(10, 0, 0, 6),
+                broadcast: Ipv4Addr::new(10, 0, 0, 7),
+                hosts: 2,
+            })
+        );
+        assert_eq!(
+            subnet_of([10, 0, 0, 7], 32).await,
+            Ok(Subnet {
+                network: Ipv4Addr::new(10, 0, 0, 7),
+                broadcast: Ipv4Addr::new(10, 0, 0, 7),
+                hosts: 1,
+            })
+        );
+        assert_eq!(
+            subnet_of([10, 0, 0, 7], 0).await.map(|subnet| subnet.hosts),
+            Ok(u32::MAX - 1)
+        );
+        assert_eq!(
+            subnet_of([10, 0, 0, 7], 33).await,
+            Err(StatusCode::BAD_REQUEST)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mid() {
+        let app = Router::new()
+            .route("/2/mid", axum::routing::get(mid))
+            .route("/2/v6/mid", axum::routing::get(mid_v6));
+        async fn get_mid(app: &Router, uri: &str) -> (StatusCode, String) {
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            (status, String::from_utf8(body.to_vec()).unwrap())
+        }
+
+        assert_eq!(
+            get_mid(&app, "/2/mid?from=10.0.0.4&to=10.0.0.5").await,
+            (StatusCode::OK, "10.0.0.4".to_string())
+        );
+        assert_eq!(
+            get_mid(&app, "/2/mid?from=10.0.0.5&to=10.0.0.4").await,
+            (StatusCode::OK, "10.0.0.4".to_string())
+        );
+        assert_eq!(
+            get_mid(&app, "/2/mid?from=0.0.0.0&to=255.255.255.255").await,
+            (StatusCode::OK, "127.255.255.255".to_string())
+        );
+        assert_eq!(
+            get_mid(&app, "/2/v6/mid?from=::1&to=::2").await,
+            (StatusCode::OK, "::1".to_string())
+        );
+        assert_eq!(
+            get_mid(
+                &app,
+                "/2/v6/mid?from=::&to=ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"
+            )
+            .await,
+            (
+                StatusCode::OK,
+                "7fff:ffff:ffff:ffff:ffff:ffff:ffff:ffff".to_string()
+            )
+        );
+
+        let (status, _) = get_mid(&app, "/2/mid?from=10.0.0.4&to=::1").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = get_mid(&app, "/2/v6/mid?from=::1&to=10.0.0.4").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = get_mid(&app, "/2/mid?from=10.0.0.256&to=10.0.0.4").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_address_field() {
+        let app = Router::new()
+            .route("/2/dest", axum::routing::get(dest))
+            .route("/2/key", axum::routing::get(key))
+            .route("/2/v6/dest", axum::routing::get(dest_v6))
+            .route("/2/subnet", axum::routing::get(subnet));
+        async fn rejection(app: &Router, uri: &str) -> serde_json::Value {
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            serde_json::from_slice(&body).unwrap()
+        }
+
+        assert_eq!(
+            rejection(&app, "/2/dest?from=1.2.3&key=5.6.7.8").await,
+            json!({ "error": "invalid address", "field": "from" })
+        );
+        assert_eq!(
+            rejection(&app, "/2/dest?from=1.2.3.4&key=banana").await,
+            json!({ "error": "invalid address", "field": "key" })
+        );
+        assert_eq!(
+            rejection(&app, "/2/key?from=10.0.0.1&to=::1").await,
+            json!({ "error": "invalid address", "field": "to" })
+        );
+        assert_eq!(
+            rejection(&app, "/2/v6/dest?from=::1&key=10.0.0.1").await,
+            json!({ "error": "invalid address", "field": "key" })
+        );
+        assert_eq!(
+            rejection(&app, "/2/subnet?addr=10.0.0.1&prefix=wide").await,
+            json!({ "error": "invalid value", "field": "prefix" })
+        );
+        assert_eq!(
+            rejection(&app, "/2/dest?from=1.2.3.4").await,
+            json!({ "error": "invalid query", "field": null })
+        );
+    }
+}
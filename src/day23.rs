@@ -1,61 +1,158 @@
 use std::fmt::Display;
 
 use axum::{
-    extract::{rejection::PathRejection, Path},
-    http::StatusCode,
-    response::Result,
+    extract::{rejection::PathRejection, Path, Query},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response, Result},
     routing::{get, post},
-    Router,
+    Json, Router,
 };
 use axum_extra::extract::Multipart;
 use hex::decode;
 use rinja::Template;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
 use tracing::error;
 
 pub fn router() -> Router {
     Router::new()
         .route("/star", get(star))
         .route("/present/:color", get(present))
+        .route("/palette", get(palette))
         .route("/ornament/:state/:n", get(ornament))
+        .route("/ornament/:state/:n/toggle", get(ornament_toggle))
         .route("/lockfile", post(lockfile))
 }
 
-async fn star() -> Star {
-    Star
+async fn star(headers: HeaderMap) -> Result<Response, StatusCode> {
+    with_etag(&headers, &Star)
 }
 
-async fn present(path: Result<Path<Color>, PathRejection>) -> Result<Present, StatusCode> {
+async fn present(
+    headers: HeaderMap,
+    path: Result<Path<Color>, PathRejection>,
+) -> Result<Response, StatusCode> {
     match path {
-        Ok(Path(color)) => Ok(Present { color }),
+        Ok(Path(color)) => with_etag(&headers, &Present { color }),
         Err(_) => Err(StatusCode::IM_A_TEAPOT),
     }
 }
 
+async fn palette() -> Json<Vec<String>> {
+    Json(Color::all().iter().map(Color::to_string).collect())
+}
+
 async fn ornament(
+    headers: HeaderMap,
     path: Result<Path<(State, String)>, PathRejection>,
-) -> Result<Ornament, StatusCode> {
+) -> Result<Response, StatusCode> {
     match path {
-        Ok(Path((state, n))) => Ok(Ornament { state, n }),
+        Ok(Path((state, n))) => with_etag(&headers, &Ornament { state, n }),
         Err(_) => Err(StatusCode::IM_A_TEAPOT),
     }
 }
 
-async fn lockfile(mut form: Multipart) -> Result<Cake, StatusCode> {
+// Previews the ornament after one toggle, i.e. what the hx-get would swap in
+async fn ornament_toggle(
+    headers: HeaderMap,
+    path: Result<Path<(State, String)>, PathRejection>,
+) -> Result<Response, StatusCode> {
+    match path {
+        Ok(Path((state, n))) => {
+            let state = Ornament {
+                state,
+                n: n.clone(),
+            }
+            .next_state();
+            with_etag(&headers, &Ornament { state, n })
+        }
+        Err(_) => Err(StatusCode::IM_A_TEAPOT),
+    }
+}
+
+// Renders a template with a strong ETag derived from its body, answering 304
+// when the client already holds the same rendering
+fn with_etag<T: Template>(headers: &HeaderMap, template: &T) -> Result<Response, StatusCode> {
+    let body = template
+        .render()
+        .inspect_err(|err| error!(%err, "failed to render template"))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // A fixed digest keeps the tag the same across builds and instances, unlike
+    // DefaultHasher. Half of SHA-256 is plenty to tell renderings apart
+    let digest = Sha256::digest(body.as_bytes());
+    let etag = format!("\"{}\"", hex::encode(&digest[..16]));
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(',')
+                .any(|candidate| candidate.trim() == etag || candidate.trim() == "*")
+        });
+    if not_modified {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+    }
+
+    Ok((
+        [
+            (header::ETAG, etag),
+            (header::CONTENT_TYPE, T::MIME_TYPE.to_string()),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+#[derive(Error, Debug, PartialEq)]
+enum LockfileError {
+    #[error("multipart form did not contain a lockfile")]
+    MissingLockfile,
+    #[error("lockfile was not valid toml")]
+    BadToml,
+    #[error("checksum was not valid hex")]
+    NonHexChecksum,
+    #[error("checksum was shorter than 5 bytes")]
+    ShortChecksum,
+}
+
+impl IntoResponse for LockfileError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            LockfileError::MissingLockfile | LockfileError::BadToml => StatusCode::BAD_REQUEST,
+            LockfileError::NonHexChecksum | LockfileError::ShortChecksum => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+#[derive(Deserialize)]
+struct LockfileQuery {
+    format: Option<String>,
+}
+
+async fn lockfile(
+    headers: HeaderMap,
+    Query(query): Query<LockfileQuery>,
+    mut form: Multipart,
+) -> Result<Response, LockfileError> {
     let field = form
         .next_field()
         .await
-        .map_err(|_| StatusCode::BAD_REQUEST)?
-        .ok_or(StatusCode::BAD_REQUEST)?;
+        .map_err(|_| LockfileError::MissingLockfile)?
+        .ok_or(LockfileError::MissingLockfile)?;
 
     let text = field
         .text()
         .await
         .inspect_err(|_| error!("failed to fetch text"))
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+        .map_err(|_| LockfileError::MissingLockfile)?;
     let lockfile: Lockfile = toml::from_str(&text)
         .inspect_err(|err| error!(%err, "failed to parse toml"))
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+        .map_err(|_| LockfileError::BadToml)?;
 
     let layers: Vec<Layer> = lockfile
         .packages
@@ -64,20 +161,51 @@ async fn lockfile(mut form: Multipart) -> Result<Cake, StatusCode> {
             let checksum = package.checksum?;
             let Ok(bytes) = decode(checksum) else {
                 error!("Invalid checksum");
-                return Some(Err(StatusCode::UNPROCESSABLE_ENTITY));
+                return Some(Err(LockfileError::NonHexChecksum));
             };
-            if bytes.len() < 5 {
+            // Only the first 5 bytes are used: color, then top and left offsets.
+            // Anything past them is ignored, anything shorter can't describe a layer
+            let Some(&[red, green, blue, top, left]) = bytes.first_chunk::<5>() else {
                 error!("Invalid checksum length");
-                return Some(Err(StatusCode::UNPROCESSABLE_ENTITY));
-            }
-            let color = format!("#{:02x}{:02x}{:02x}", bytes[0], bytes[1], bytes[2]);
-            let top = bytes[3];
-            let left = bytes[4];
+                return Some(Err(LockfileError::ShortChecksum));
+            };
+            let color = format!("#{red:02x}{green:02x}{blue:02x}");
             Some(Ok(Layer { color, top, left }))
         })
         .collect::<Result<Vec<_>, _>>()?;
 
-    Ok(Cake { layers })
+    let accept = headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .unwrap_or_default();
+    if accept.contains("application/json") {
+        return Ok(Json(layers).into_response());
+    }
+    if query.format.as_deref() == Some("svg") || accept.contains("image/svg+xml") {
+        return Ok((
+            [(header::CONTENT_TYPE, "image/svg+xml")],
+            render_svg(&layers),
+        )
+            .into_response());
+    }
+
+    Ok(Cake { layers }.into_response())
+}
+
+// Mirrors the 276px lockfile canvas and 20px sprinkles of the HTML version
+fn render_svg(layers: &[Layer]) -> String {
+    let rects: String = layers
+        .iter()
+        .map(|layer| {
+            format!(
+                r#"<rect x="{}" y="{}" width="20" height="20" rx="10" fill="{}"/>"#,
+                layer.left, layer.top, layer.color
+            )
+        })
+        .collect();
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="276" height="276" viewBox="0 0 276 276">{rects}</svg>"#
+    )
 }
 
 #[derive(Template)]
@@ -92,20 +220,38 @@ struct Present {
 
 impl Present {
     fn next_color(&self) -> Color {
-        match self.color {
-            Color::Red => Color::Blue,
-            Color::Blue => Color::Purple,
-            Color::Purple => Color::Red,
-        }
+        let colors = Color::all();
+        let index = colors
+            .iter()
+            .position(|color| *color == self.color)
+            .expect("every color is in the palette");
+        colors[(index + 1) % colors.len()]
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Copy, PartialEq)]
 #[serde(rename_all = "lowercase")]
 enum Color {
     Red,
     Blue,
     Purple,
+    Green,
+    Yellow,
+    Orange,
+}
+
+impl Color {
+    // Rotation order for presents
+    fn all() -> [Color; 6] {
+        [
+            Color::Red,
+            Color::Blue,
+            Color::Purple,
+            Color::Green,
+            Color::Yellow,
+            Color::Orange,
+        ]
+    }
 }
 
 impl Display for Color {
@@ -114,6 +260,9 @@ impl Display for Color {
             Color::Red => "red",
             Color::Blue => "blue",
             Color::Purple => "purple",
+            Color::Green => "green",
+            Color::Yellow => "yellow",
+            Color::Orange => "orange",
         };
         write!(f, "{}", s)
     }
@@ -165,6 +314,7 @@ struct Cake {
     layers: Vec<Layer>,
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 struct Layer {
     color: String,
     top: u8,
@@ -181,3 +331,334 @@ struct Lockfile {
 struct Package {
     checksum: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{body::Body, extract::Request};
+    use http_body_util::BodyExt;
+    use tower::Service;
+
+    async fn post_lockfile(app: &mut Router, lockfile: &str, accept: &str) -> Response {
+        let body = format!(
+            "--BOUNDARY\r\n\
+             Content-Disposition: form-data; name=\"lockfile\"; filename=\"Cargo.lock\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\n\
+             {lockfile}\r\n\
+             --BOUNDARY--\r\n"
+        );
+        app.call(
+            Request::builder()
+                .method("POST")
+                .uri("/lockfile")
+                .header(
+                    header::CONTENT_TYPE,
+                    "multipart/form-data; boundary=BOUNDARY",
+                )
+                .header(header::ACCEPT, accept)
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_lockfile_errors() {
+        let mut app = router();
+        let cases = [
+            (
+                "[[package]\nname = ",
+                StatusCode::BAD_REQUEST,
+                "lockfile was not valid toml",
+            ),
+            (
+                "[[package]]\nchecksum = \"not hex\"",
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "checksum was not valid hex",
+            ),
+            (
+                "[[package]]\nchecksum = \"abcdef\"",
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "checksum was shorter than 5 bytes",
+            ),
+        ];
+
+        for (lockfile, status, message) in cases {
+            let response = post_lockfile(&mut app, lockfile, "text/html").await;
+            assert_eq!(response.status(), status);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(body, message);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lockfile_checksum_length() {
+        let mut app = router();
+
+        let response = post_lockfile(
+            &mut app,
+            "[[package]]\nchecksum = \"01020304\"",
+            "application/json",
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        for checksum in [
+            "0102030405",
+            "0102030405060708090a",
+            "0102030405ffffffffffffffffffff",
+        ] {
+            let lockfile = format!("[[package]]\nchecksum = \"{checksum}\"");
+            let response = post_lockfile(&mut app, &lockfile, "application/json").await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let layers: Vec<Layer> = serde_json::from_slice(&body).unwrap();
+            assert_eq!(
+                layers,
+                vec![Layer {
+                    color: "#010203".to_string(),
+                    top: 4,
+                    left: 5,
+                }]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lockfile_json() {
+        let mut app = router();
+        let lockfile = r#"
+            [[package]]
+            name = "shuttle-cch2024"
+
+            [[package]]
+            name = "addr2line"
+            checksum = "337415695d9c3ad4ef30fcb7a1b9d2a1d6ed0d8b6a6fbd8b2b9d0e4c4d3b2a1f"
+
+            [[package]]
+            name = "adler2"
+            checksum = "ff0a11b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e"
+        "#;
+
+        let response = post_lockfile(&mut app, lockfile, "application/json").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let layers: Vec<Layer> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            layers,
+            vec![
+                Layer {
+                    color: "#337415".to_string(),
+                    top: 0x69,
+                    left: 0x5d,
+                },
+                Layer {
+                    color: "#ff0a11".to_string(),
+                    top: 0xb2,
+                    left: 0xc3,
+                },
+            ]
+        );
+
+        let response = post_lockfile(&mut app, lockfile, "text/html").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("background-color:#337415;top:105px;left:93px;"));
+    }
+
+    #[tokio::test]
+    async fn test_lockfile_svg() {
+        let layers = [
+            Layer {
+                color: "#337415".to_string(),
+                top: 0x69,
+                left: 0x5d,
+            },
+            Layer {
+                color: "#ff0a11".to_string(),
+                top: 0xb2,
+                left: 0xc3,
+            },
+        ];
+        let svg = render_svg(&layers);
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<rect").count(), 2);
+        assert!(svg
+            .contains(r##"<rect x="93" y="105" width="20" height="20" rx="10" fill="#337415"/>"##));
+        assert!(svg.contains(r##"fill="#ff0a11""##));
+
+        let mut app = router();
+        let lockfile = "[[package]]\nchecksum = \"337415695d9c3ad4ef30\"";
+        let response = post_lockfile(&mut app, lockfile, "image/svg+xml").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/svg+xml");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body.as_ref(), render_svg(&layers[..1]).as_bytes());
+    }
+
+    #[test]
+    fn test_present_next_color() {
+        let transitions = [
+            (Color::Purple, "green"),
+            (Color::Green, "yellow"),
+            (Color::Yellow, "orange"),
+            (Color::Orange, "red"),
+        ];
+        for (color, next) in transitions {
+            assert_eq!(Present { color }.next_color().to_string(), next);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_palette() {
+        let mut app = router();
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/palette")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let palette: Vec<String> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            palette,
+            ["red", "blue", "purple", "green", "yellow", "orange"]
+        );
+
+        for (index, color) in palette.iter().enumerate() {
+            let response = app
+                .call(
+                    Request::builder()
+                        .uri(format!("/present/{color}"))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let body = String::from_utf8(body.to_vec()).unwrap();
+            let next = &palette[(index + 1) % palette.len()];
+            assert!(body.contains(&format!(r#"hx-get="/23/present/{next}""#)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_present_colors() {
+        let mut app = router();
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/present/green")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains(r#"class="present green""#));
+        assert!(body.contains(r#"hx-get="/23/present/yellow""#));
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/present/teal")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::IM_A_TEAPOT);
+    }
+
+    #[tokio::test]
+    async fn test_star_etag_is_stable() {
+        let response = router()
+            .call(Request::builder().uri("/star").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        // Pinned so a change of digest, which would invalidate every cached copy, is deliberate
+        assert_eq!(
+            response.headers()[header::ETAG],
+            "\"bfade20f268586ed008a10c03d684a60\""
+        );
+    }
+
+    #[tokio::test]
+    async fn test_present_etag() {
+        let mut app = router();
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/present/red")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers()[header::ETAG].clone();
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/present/red")
+                    .header(header::IF_NONE_MATCH, etag.clone())
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], etag);
+
+        let response = app
+            .call(
+                Request::builder()
+                    .uri("/present/blue")
+                    .header(header::IF_NONE_MATCH, etag)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_ornament_toggle() {
+        let mut app = router();
+        let mut get_body = |uri: &'static str| {
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            let response = app.call(request);
+            async move {
+                let response = response.await.unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let body = response.into_body().collect().await.unwrap().to_bytes();
+                String::from_utf8(body.to_vec()).unwrap()
+            }
+        };
+
+        let body = get_body("/ornament/on/1/toggle").await;
+        assert!(body.contains(r#"class="ornament""#));
+        assert!(body.contains(r#"hx-get="/23/ornament/on/1""#));
+        assert_eq!(body, get_body("/ornament/off/1").await);
+
+        let body = get_body("/ornament/off/1/toggle").await;
+        assert!(body.contains(r#"class="ornament on""#));
+        assert!(body.contains(r#"hx-get="/23/ornament/off/1""#));
+        assert_eq!(body, get_body("/ornament/on/1").await);
+    }
+}
@@ -1,11 +1,29 @@
+use std::{fmt::Display, io::Read};
+
 use axum::{
-    http::{HeaderMap, StatusCode},
-    response::IntoResponse,
+    body::Bytes,
+    extract::Query,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
 };
+use axum_extra::extract::Multipart;
 use cargo_manifest::Manifest;
+use flate2::read::GzDecoder;
+use rinja::Template;
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 use thiserror::Error;
 use toml::Table;
 use tracing::{error, instrument};
+use utoipa::{IntoParams, OpenApi};
+
+mod xml;
+
+#[derive(OpenApi)]
+#[openapi(paths(manifest, combine, validate, schema))]
+pub struct ApiDoc;
 
 #[derive(Error, Debug, PartialEq)]
 pub enum ManifestError {
@@ -15,6 +33,8 @@ pub enum ManifestError {
     InvalidJson,
     #[error("yaml was not valid")]
     InvalidYaml,
+    #[error("xml was not valid")]
+    InvalidXml,
     #[error("unsupported manifest format")]
     Unsupported,
     #[error("toml was not a valid cargo manifest")]
@@ -23,6 +43,16 @@ pub enum ManifestError {
     NotChristmas,
     #[error("no valid orders found")]
     NoOrders,
+    #[error("multipart form was not valid")]
+    InvalidForm,
+    #[error("body could not be decoded")]
+    InvalidEncoding,
+    #[error("decompressed body was too large")]
+    TooLarge,
+    #[error("unknown top-level tables: {}", .0.join(", "))]
+    UnknownTables(Vec<String>),
+    #[error("{} orders were rejected", .0.len())]
+    RejectedOrders(Vec<RejectedOrder>),
 }
 
 impl IntoResponse for ManifestError {
@@ -31,6 +61,7 @@ impl IntoResponse for ManifestError {
             ManifestError::InvalidToml => StatusCode::NO_CONTENT.into_response(),
             ManifestError::InvalidJson => StatusCode::NO_CONTENT.into_response(),
             ManifestError::InvalidYaml => StatusCode::NO_CONTENT.into_response(),
+            ManifestError::InvalidXml => StatusCode::NO_CONTENT.into_response(),
             ManifestError::Unsupported => StatusCode::UNSUPPORTED_MEDIA_TYPE.into_response(),
             ManifestError::InvalidCargoToml => {
                 (StatusCode::BAD_REQUEST, "Invalid manifest").into_response()
@@ -39,12 +70,102 @@ impl IntoResponse for ManifestError {
                 (StatusCode::BAD_REQUEST, "Magic keyword not provided").into_response()
             }
             ManifestError::NoOrders => StatusCode::NO_CONTENT.into_response(),
+            ManifestError::InvalidForm => StatusCode::BAD_REQUEST.into_response(),
+            ManifestError::InvalidEncoding => StatusCode::BAD_REQUEST.into_response(),
+            ManifestError::TooLarge => StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+            ManifestError::UnknownTables(keys) => (
+                StatusCode::BAD_REQUEST,
+                format!("Unknown top-level tables: {}", keys.join(", ")),
+            )
+                .into_response(),
+            ManifestError::RejectedOrders(rejected) => (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "rejected": rejected })),
+            )
+                .into_response(),
         }
     }
 }
 
-fn manifest_json(json: String) -> Result<String, ManifestError> {
-    let mut deserializer = serde_json::Deserializer::from_str(&json);
+// Bodies past this are rejected with 413 before reaching the transcoders
+pub const MANIFEST_BODY_LIMIT: usize = 1024 * 1024;
+
+// The body limit /manifest is served with, which gzipped bodies are held to once decoded
+#[derive(Clone, Copy, Debug)]
+pub struct ManifestLimit(pub usize);
+
+impl Default for ManifestLimit {
+    fn default() -> Self {
+        Self(MANIFEST_BODY_LIMIT)
+    }
+}
+
+// Media types seen in the wild for each format, matched against the lowercased essence
+const TOML_TYPES: &[&str] = &["application/toml", "application/x-toml", "text/toml"];
+const JSON_TYPES: &[&str] = &["application/json"];
+const YAML_TYPES: &[&str] = &["application/yaml"];
+const XML_TYPES: &[&str] = &["application/xml"];
+
+const KNOWN_TABLES: &[&str] = &[
+    "cargo-features",
+    "package",
+    "lib",
+    "bin",
+    "example",
+    "test",
+    "bench",
+    "dependencies",
+    "dev-dependencies",
+    "build-dependencies",
+    "target",
+    "badges",
+    "features",
+    "lints",
+    "patch",
+    "replace",
+    "profile",
+    "workspace",
+];
+
+#[derive(Deserialize, IntoParams, Default)]
+#[into_params(parameter_in = Query)]
+pub struct ManifestOpts {
+    #[serde(default)]
+    strict_tables: bool,
+    #[serde(default)]
+    verbose: bool,
+    #[serde(default)]
+    total: bool,
+    // Reads package.metadata.<profile>.orders instead of package.metadata.orders
+    profile: Option<String>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct RejectedOrder {
+    pub index: usize,
+    pub reason: &'static str,
+}
+
+#[derive(Serialize, JsonSchema, Debug, PartialEq)]
+pub struct Order {
+    pub item: String,
+    pub quantity: i64,
+}
+
+impl Display for Order {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.item, self.quantity)
+    }
+}
+
+#[derive(Template)]
+#[template(path = "orders.html")]
+struct ShoppingList {
+    orders: Vec<Order>,
+}
+
+fn manifest_json(json: &str) -> Result<String, ManifestError> {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
     let mut toml_string = String::new();
     let serializer = toml::Serializer::new(&mut toml_string);
     serde_transcode::transcode(&mut deserializer, serializer)
@@ -54,8 +175,8 @@ fn manifest_json(json: String) -> Result<String, ManifestError> {
     Ok(toml_string)
 }
 
-fn manifest_yaml(yaml: String) -> Result<String, ManifestError> {
-    let deserializer = serde_yaml::Deserializer::from_str(&yaml);
+fn manifest_yaml(yaml: &str) -> Result<String, ManifestError> {
+    let deserializer = serde_yaml::Deserializer::from_str(yaml);
     let mut toml_string = String::new();
     let serializer = toml::Serializer::new(&mut toml_string);
     serde_transcode::transcode(deserializer, serializer)
@@ -65,19 +186,237 @@ fn manifest_yaml(yaml: String) -> Result<String, ManifestError> {
     Ok(toml_string)
 }
 
+fn manifest_xml(xml: &str) -> Result<String, ManifestError> {
+    let table = xml::to_table(xml)
+        .inspect_err(|error| error!(%error, "xml was not valid"))
+        .map_err(|_| ManifestError::InvalidXml)?;
+    toml::to_string(&table)
+        .inspect_err(|error| error!(%error, "xml was not valid"))
+        .map_err(|_| ManifestError::InvalidXml)
+}
+
+#[instrument(ret, skip_all)]
+#[utoipa::path(
+    post,
+    path = "/manifest",
+    params(ManifestOpts),
+    request_body(content = String, description = "Cargo manifest as TOML, JSON, YAML or XML"),
+    responses(
+        (status = OK, body = String, description = "One order per line, or JSON for Accept: application/json"),
+        (status = NO_CONTENT),
+        (status = BAD_REQUEST),
+        (status = PAYLOAD_TOO_LARGE),
+        (status = UNSUPPORTED_MEDIA_TYPE),
+    ),
+)]
+pub async fn manifest(
+    headers: HeaderMap,
+    Extension(limit): Extension<ManifestLimit>,
+    Query(opts): Query<ManifestOpts>,
+    body: Bytes,
+) -> Result<Response, ManifestError> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .unwrap_or_default();
+    let body = decode_body(&headers, body, limit)?;
+    let orders = parse_manifest_with(content_type, &body, &opts)?;
+    Ok(render_orders(&headers, orders, opts.total))
+}
+
+fn decode_body(
+    headers: &HeaderMap,
+    body: Bytes,
+    ManifestLimit(limit): ManifestLimit,
+) -> Result<String, ManifestError> {
+    let gzipped = headers
+        .get(header::CONTENT_ENCODING)
+        .is_some_and(|encoding| encoding == "gzip");
+    let body = if gzipped {
+        // The body limit only saw the compressed bytes, so cap what the decoder may expand to
+        let mut decoded = Vec::new();
+        GzDecoder::new(body.as_ref())
+            .take(limit as u64 + 1)
+            .read_to_end(&mut decoded)
+            .inspect_err(|error| error!(%error, "gzip body was not valid"))
+            .map_err(|_| ManifestError::InvalidEncoding)?;
+        if decoded.len() > limit {
+            error!("decompressed body exceeded {limit} bytes");
+            return Err(ManifestError::TooLarge);
+        }
+        decoded
+    } else {
+        body.into()
+    };
+    String::from_utf8(body)
+        .inspect_err(|error| error!(%error, "body was not utf-8"))
+        .map_err(|_| ManifestError::InvalidEncoding)
+}
+
+#[instrument(ret, skip_all)]
+#[utoipa::path(
+    post,
+    path = "/validate",
+    params(ManifestOpts),
+    request_body = String,
+    responses(
+        (status = OK),
+        (status = NO_CONTENT),
+        (status = BAD_REQUEST),
+        (status = UNSUPPORTED_MEDIA_TYPE),
+    ),
+)]
+pub async fn validate(
+    headers: HeaderMap,
+    Query(opts): Query<ManifestOpts>,
+    body: String,
+) -> Result<Json<serde_json::Value>, ManifestError> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .unwrap_or_default();
+    parse_christmas_manifest(content_type, &body, &opts)?;
+    Ok(Json(json!({ "valid": true })))
+}
+
+// The shape expected under package.metadata
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct OrdersMetadata {
+    orders: Vec<Order>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/schema",
+    responses((status = OK, description = "JSON Schema for package.metadata")),
+)]
+pub async fn schema() -> Json<schemars::schema::RootSchema> {
+    Json(schema_for!(OrdersMetadata))
+}
+
 #[instrument(ret, skip_all)]
-pub async fn manifest(headers: HeaderMap, body: String) -> Result<String, ManifestError> {
-    let toml = match headers.get("Content-Type") {
-        Some(content_type) if content_type == "application/json" => manifest_json(body),
-        Some(content_type) if content_type == "application/yaml" => manifest_yaml(body),
-        Some(content_type) if content_type == "application/toml" => Ok(body),
-        _ => Err(ManifestError::Unsupported),
+#[utoipa::path(
+    post,
+    path = "/manifest/combine",
+    request_body(content = String, content_type = "multipart/form-data"),
+    responses(
+        (status = OK, body = String),
+        (status = NO_CONTENT),
+        (status = BAD_REQUEST),
+    ),
+)]
+pub async fn combine(headers: HeaderMap, mut form: Multipart) -> Result<Response, ManifestError> {
+    let mut orders = Vec::new();
+    while let Some(field) = form
+        .next_field()
+        .await
+        .inspect_err(|error| error!(%error, "multipart form was not valid"))
+        .map_err(|_| ManifestError::InvalidForm)?
+    {
+        let content_type = field.content_type().unwrap_or_default().to_string();
+        let body = field
+            .text()
+            .await
+            .inspect_err(|error| error!(%error, "failed to read manifest part"))
+            .map_err(|_| ManifestError::InvalidForm)?;
+        orders.extend(parse_manifest(&content_type, &body)?);
+    }
+
+    if orders.is_empty() {
+        error!("no manifests were submitted");
+        return Err(ManifestError::NoOrders);
+    }
+
+    Ok(render_orders(&headers, orders, false))
+}
+
+pub fn parse_manifest(content_type: &str, body: &str) -> Result<Vec<Order>, ManifestError> {
+    parse_manifest_with(content_type, body, &ManifestOpts::default())
+}
+
+fn parse_manifest_with(
+    content_type: &str,
+    body: &str,
+    opts: &ManifestOpts,
+) -> Result<Vec<Order>, ManifestError> {
+    let manifest = parse_christmas_manifest(content_type, body, opts)?;
+
+    // Inline `orders = [{ .. }]` and `[[package.metadata.orders]]` both parse to an array of tables
+    let metadata = manifest
+        .get("package")
+        .and_then(|value| value.get("metadata"));
+    let group = match opts.profile.as_deref() {
+        Some(profile) => metadata.and_then(|value| value.get(profile)),
+        None => metadata,
+    };
+    let orders = group
+        .and_then(|value| value.get("orders"))
+        .and_then(|value| value.as_array())
+        .ok_or(ManifestError::NoOrders)
+        .inspect_err(|_| error!(profile = ?opts.profile, "orders not present"))?;
+
+    let mut valid_orders = Vec::new();
+    let mut rejected = Vec::new();
+    for (index, order) in orders.iter().enumerate() {
+        match validate_order(order) {
+            Ok(order) => valid_orders.push(order),
+            Err(reason) => rejected.push(RejectedOrder { index, reason }),
+        }
+    }
+
+    if opts.verbose && !rejected.is_empty() {
+        error!(?rejected, "manifest contained rejected orders");
+        return Err(ManifestError::RejectedOrders(rejected));
+    }
+
+    if valid_orders.is_empty() {
+        error!("manifest contained no valid orders");
+        return Err(ManifestError::NoOrders);
+    }
+
+    Ok(valid_orders)
+}
+
+fn parse_christmas_manifest(
+    content_type: &str,
+    body: &str,
+    opts: &ManifestOpts,
+) -> Result<Table, ManifestError> {
+    // Parameters like charset don't change the format, and media types are case-insensitive
+    let essence = content_type
+        .parse::<mime::Mime>()
+        .map(|mime| mime.essence_str().to_ascii_lowercase())
+        .unwrap_or_default();
+    let essence = essence.as_str();
+    let toml = if JSON_TYPES.contains(&essence) {
+        manifest_json(body)
+    } else if YAML_TYPES.contains(&essence) {
+        manifest_yaml(body)
+    } else if XML_TYPES.contains(&essence) {
+        manifest_xml(body)
+    } else if TOML_TYPES.contains(&essence) {
+        Ok(body.to_string())
+    } else {
+        Err(ManifestError::Unsupported)
     }?;
     let manifest: Table = toml
         .parse()
         .inspect_err(|error| error!(%error, "toml was not valid"))
         .map_err(|_| ManifestError::InvalidToml)?;
 
+    if opts.strict_tables {
+        let unknown: Vec<String> = manifest
+            .keys()
+            .filter(|key| !KNOWN_TABLES.contains(&key.as_str()))
+            .cloned()
+            .collect();
+        if !unknown.is_empty() {
+            error!(?unknown, "manifest contained unknown top-level tables");
+            return Err(ManifestError::UnknownTables(unknown));
+        }
+    }
+
     if Manifest::from_slice(toml.as_bytes()).is_err() {
         error!("toml was not a valid Cargo.toml");
         return Err(ManifestError::InvalidCargoToml);
@@ -98,35 +437,63 @@ pub async fn manifest(headers: HeaderMap, body: String) -> Result<String, Manife
         return Err(ManifestError::NotChristmas);
     }
 
-    let orders = manifest
-        .get("package")
-        .and_then(|value| value.get("metadata"))
-        .and_then(|value| value.get("orders"))
-        .and_then(|value| value.as_array())
-        .ok_or(ManifestError::NoOrders)
-        .inspect_err(|_| error!("package.metadata.orders not present"))?;
-
-    let valid_orders: Vec<String> = orders
-        .iter()
-        .filter_map(|order| {
-            let item = order.get("item").and_then(|value| value.as_str())?;
-            let quantity = order.get("quantity").and_then(|value| value.as_integer())?;
-            Some(format!("{}: {}", item, quantity))
-        })
-        .collect();
+    Ok(manifest)
+}
 
-    if valid_orders.is_empty() {
-        error!("manifest contained no valid orders");
-        return Err(ManifestError::NoOrders);
+fn validate_order(order: &toml::Value) -> Result<Order, &'static str> {
+    let item = order.get("item").ok_or("missing item")?;
+    let item = item.as_str().ok_or("item must be a string")?;
+    let quantity = order.get("quantity").ok_or("missing quantity")?;
+    let quantity = quantity.as_integer().ok_or("quantity must be an integer")?;
+    Ok(Order {
+        item: item.to_string(),
+        quantity,
+    })
+}
+
+fn render_orders(headers: &HeaderMap, orders: Vec<Order>, total: bool) -> Response {
+    let accept = headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .unwrap_or_default();
+    if accept.contains("text/html") {
+        return ShoppingList { orders }.into_response();
     }
-    let answer = valid_orders.join("\n");
-    Ok(answer)
+    if accept.contains("application/json") {
+        return Json(orders).into_response();
+    }
+
+    let mut lines: Vec<String> = orders.iter().map(Order::to_string).collect();
+    if total {
+        // Summed wider than i64 so large quantities can't overflow
+        let sum: i128 = orders.iter().map(|order| i128::from(order.quantity)).sum();
+        lines.push(format!("Total: {sum}"));
+    }
+    lines.join("\n").into_response()
 }
 
 #[cfg(test)]
 mod test {
-    use axum::http::HeaderMap;
+    use axum::{
+        body::Body,
+        extract::{Query, Request},
+        http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
+        response::{IntoResponse, Response},
+        routing::post,
+        Extension, Json, Router,
+    };
+    use http_body_util::BodyExt;
+    use proptest::prelude::*;
+    use serde_json::json;
     use toml::toml;
+    use tower::ServiceExt;
+
+    use super::{parse_manifest, ManifestError, ManifestLimit, ManifestOpts, Order, RejectedOrder};
+
+    async fn body_text(response: Response) -> String {
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
 
     #[tokio::test]
     async fn test_manifest() {
@@ -146,8 +513,542 @@ mod test {
         "#;
         let mut headers = HeaderMap::new();
         headers.insert("Content-Type", "application/toml".parse().unwrap());
-        let actual = super::manifest(headers, toml.to_string()).await.unwrap();
-        assert_eq!(actual, "Toy car: 2\nLego brick: 230");
+        let response = super::manifest(
+            headers,
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            toml.into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body_text(response).await, "Toy car: 2\nLego brick: 230");
+    }
+
+    #[tokio::test]
+    async fn test_manifest_gzip() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let toml = r#"
+            [package]
+            name = "not-a-gift-order"
+            authors = ["Not Santa"]
+            keywords = ["Christmas 2024"]
+
+            [[package.metadata.orders]]
+            item = "Toy car"
+            quantity = 2
+
+            [[package.metadata.orders]]
+            item = "Lego brick"
+            quantity = 230
+        "#;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(toml.as_bytes()).unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "application/toml".parse().unwrap());
+        let plain = super::manifest(
+            headers.clone(),
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            toml.into(),
+        )
+        .await
+        .unwrap();
+
+        headers.insert("Content-Encoding", "gzip".parse().unwrap());
+        let response = super::manifest(
+            headers.clone(),
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            gzipped.into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body_text(response).await, body_text(plain).await);
+
+        let response = super::manifest(
+            headers,
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            toml.into(),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_manifest_gzip_bomb() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        // A few KiB of gzip that expands past the body limit
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder
+            .write_all(&vec![b' '; super::MANIFEST_BODY_LIMIT * 4])
+            .unwrap();
+        let gzipped = encoder.finish().unwrap();
+        assert!(gzipped.len() < super::MANIFEST_BODY_LIMIT / 100);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "application/toml".parse().unwrap());
+        headers.insert("Content-Encoding", "gzip".parse().unwrap());
+        let response = super::manifest(
+            headers,
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            gzipped.into(),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_manifest_gzip_raised_limit() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        // A valid manifest that decodes to twice the default limit
+        let mut toml = r#"
+            [package]
+            name = "not-a-gift-order"
+            authors = ["Not Santa"]
+            keywords = ["Christmas 2024"]
+
+            [[package.metadata.orders]]
+            item = "Toy car"
+            quantity = 2
+        "#
+        .to_string();
+        toml.push_str(&" ".repeat(super::MANIFEST_BODY_LIMIT * 2));
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(toml.as_bytes()).unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "application/toml".parse().unwrap());
+        headers.insert("Content-Encoding", "gzip".parse().unwrap());
+        let actual = super::manifest(
+            headers.clone(),
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            gzipped.clone().into(),
+        )
+        .await;
+        assert_eq!(actual.unwrap_err(), ManifestError::TooLarge);
+
+        let raised = ManifestLimit(super::MANIFEST_BODY_LIMIT * 4);
+        let response = super::manifest(
+            headers,
+            Extension(raised),
+            Query(Default::default()),
+            gzipped.into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body_text(response).await, "Toy car: 2");
+    }
+
+    #[tokio::test]
+    async fn test_manifest_json_output() {
+        let toml = r#"
+            [package]
+            name = "not-a-gift-order"
+            authors = ["Not Santa"]
+            keywords = ["Christmas 2024"]
+
+            [[package.metadata.orders]]
+            item = "Toy car"
+            quantity = 2
+
+            [[package.metadata.orders]]
+            item = "Lego brick"
+            quantity = 230
+        "#;
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "application/toml".parse().unwrap());
+        let response = super::manifest(
+            headers.clone(),
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            toml.into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body_text(response).await, "Toy car: 2\nLego brick: 230");
+
+        headers.insert("Accept", "application/json".parse().unwrap());
+        let response = super::manifest(
+            headers,
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            toml.into(),
+        )
+        .await
+        .unwrap();
+        let orders: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(
+            orders,
+            serde_json::json!([
+                { "item": "Toy car", "quantity": 2 },
+                { "item": "Lego brick", "quantity": 230 },
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_manifest_total() {
+        let toml = r#"
+            [package]
+            name = "not-a-gift-order"
+            authors = ["Not Santa"]
+            keywords = ["Christmas 2024"]
+
+            [[package.metadata.orders]]
+            item = "Toy car"
+            quantity = 2
+
+            [[package.metadata.orders]]
+            item = "Lego brick"
+            quantity = 230
+
+            [[package.metadata.orders]]
+            item = "Teddy bear"
+            quantity = 8
+        "#;
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "application/toml".parse().unwrap());
+
+        let response = super::manifest(
+            headers.clone(),
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            toml.into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            body_text(response).await,
+            "Toy car: 2\nLego brick: 230\nTeddy bear: 8"
+        );
+
+        let opts = ManifestOpts {
+            total: true,
+            ..Default::default()
+        };
+        let response = super::manifest(
+            headers,
+            Extension(ManifestLimit::default()),
+            Query(opts),
+            toml.into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            body_text(response).await,
+            "Toy car: 2\nLego brick: 230\nTeddy bear: 8\nTotal: 240"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_manifest_profile() {
+        let toml = r#"
+            [package]
+            name = "not-a-gift-order"
+            authors = ["Not Santa"]
+            keywords = ["Christmas 2024"]
+
+            [[package.metadata.orders]]
+            item = "Toy car"
+            quantity = 2
+
+            [[package.metadata.naughty.orders]]
+            item = "Lump of coal"
+            quantity = 1
+        "#;
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "application/toml".parse().unwrap());
+
+        let response = super::manifest(
+            headers.clone(),
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            toml.into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body_text(response).await, "Toy car: 2");
+
+        let naughty = ManifestOpts {
+            profile: Some("naughty".to_string()),
+            ..Default::default()
+        };
+        let response = super::manifest(
+            headers.clone(),
+            Extension(ManifestLimit::default()),
+            Query(naughty),
+            toml.into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body_text(response).await, "Lump of coal: 1");
+
+        let missing = ManifestOpts {
+            profile: Some("nice".to_string()),
+            ..Default::default()
+        };
+        let actual = super::manifest(
+            headers,
+            Extension(ManifestLimit::default()),
+            Query(missing),
+            toml.into(),
+        )
+        .await;
+        assert_eq!(actual.unwrap_err(), ManifestError::NoOrders);
+    }
+
+    #[tokio::test]
+    async fn test_manifest_inline_orders() {
+        let toml = r#"
+            [package]
+            name = "not-a-gift-order"
+            authors = ["Not Santa"]
+            keywords = ["Christmas 2024"]
+            metadata = { orders = [{ item = "Toy car", quantity = 2 }] }
+        "#;
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "application/toml".parse().unwrap());
+        let response = super::manifest(
+            headers.clone(),
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            toml.into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body_text(response).await, "Toy car: 2");
+
+        let toml = r#"
+            [package]
+            name = "not-a-gift-order"
+            authors = ["Not Santa"]
+            keywords = ["Christmas 2024"]
+
+            [package.metadata]
+            orders = [
+                { item = "Toy car", quantity = 2 },
+                { item = "Lego brick", quantity = 230 },
+            ]
+        "#;
+        let response = super::manifest(
+            headers,
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            toml.into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body_text(response).await, "Toy car: 2\nLego brick: 230");
+    }
+
+    #[tokio::test]
+    async fn test_manifest_xml() {
+        let xml = r#"
+            <manifest>
+                <package>
+                    <name>not-a-gift-order</name>
+                    <authors>Not Santa</authors>
+                    <keywords>Christmas 2024</keywords>
+                    <metadata>
+                        <orders>
+                            <item>Toy car</item>
+                            <quantity>2</quantity>
+                        </orders>
+                        <orders>
+                            <item>Lego brick</item>
+                            <quantity>230</quantity>
+                        </orders>
+                    </metadata>
+                </package>
+            </manifest>
+        "#;
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "application/xml".parse().unwrap());
+        let response = super::manifest(
+            headers,
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            xml.into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body_text(response).await, "Toy car: 2\nLego brick: 230");
+    }
+
+    #[tokio::test]
+    async fn test_manifest_xml_text_stays_text() {
+        let xml = r#"
+            <manifest>
+                <package>
+                    <name>not-a-gift-order</name>
+                    <keywords>Christmas 2024</keywords>
+                    <keywords>true</keywords>
+                    <metadata>
+                        <orders>
+                            <item>42</item>
+                            <quantity>3</quantity>
+                        </orders>
+                    </metadata>
+                </package>
+            </manifest>
+        "#;
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "application/xml".parse().unwrap());
+        let response = super::manifest(
+            headers.clone(),
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            xml.into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body_text(response).await, "42: 3");
+
+        let actual = super::manifest(
+            headers,
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            "<manifest><package>".into(),
+        )
+        .await;
+        assert_eq!(actual.unwrap_err(), ManifestError::InvalidXml);
+    }
+
+    #[tokio::test]
+    async fn test_manifest_xml_options() {
+        let xml = r#"
+            <manifest>
+                <package>
+                    <name>not-a-gift-order</name>
+                    <keywords>Christmas 2024</keywords>
+                    <metadata>
+                        <orders>
+                            <item>Toy car</item>
+                            <quantity>2</quantity>
+                        </orders>
+                        <orders>
+                            <item>Teddy bear</item>
+                            <quantity>lots</quantity>
+                        </orders>
+                        <naughty>
+                            <orders>
+                                <item>Lump of coal</item>
+                                <quantity>1</quantity>
+                            </orders>
+                        </naughty>
+                    </metadata>
+                </package>
+                <oops><typo>true</typo></oops>
+            </manifest>
+        "#;
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "application/xml".parse().unwrap());
+
+        let response = super::manifest(
+            headers.clone(),
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            xml.into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body_text(response).await, "Toy car: 2");
+
+        let naughty = ManifestOpts {
+            profile: Some("naughty".to_string()),
+            ..Default::default()
+        };
+        let response = super::manifest(
+            headers.clone(),
+            Extension(ManifestLimit::default()),
+            Query(naughty),
+            xml.into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body_text(response).await, "Lump of coal: 1");
+
+        let strict = ManifestOpts {
+            strict_tables: true,
+            ..Default::default()
+        };
+        let actual = super::manifest(
+            headers.clone(),
+            Extension(ManifestLimit::default()),
+            Query(strict),
+            xml.into(),
+        )
+        .await;
+        assert_eq!(
+            actual.unwrap_err(),
+            ManifestError::UnknownTables(vec!["oops".to_string()])
+        );
+
+        let verbose = ManifestOpts {
+            verbose: true,
+            ..Default::default()
+        };
+        let actual = super::manifest(
+            headers,
+            Extension(ManifestLimit::default()),
+            Query(verbose),
+            xml.into(),
+        )
+        .await;
+        assert_eq!(
+            actual.unwrap_err(),
+            ManifestError::RejectedOrders(vec![RejectedOrder {
+                index: 1,
+                reason: "quantity must be an integer",
+            }])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_manifest_html() {
+        let toml = r#"
+            [package]
+            name = "not-a-gift-order"
+            authors = ["Not Santa"]
+            keywords = ["Christmas 2024"]
+
+            [[package.metadata.orders]]
+            item = "Toy car"
+            quantity = 2
+
+            [[package.metadata.orders]]
+            item = "Lego brick"
+            quantity = 230
+        "#;
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "application/toml".parse().unwrap());
+        headers.insert("Accept", "text/html".parse().unwrap());
+        let response = super::manifest(
+            headers,
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            toml.into(),
+        )
+        .await
+        .unwrap();
+        let actual = body_text(response).await;
+        assert_eq!(actual.matches("<li>").count(), 2);
+        assert!(actual.contains(r#"<li><input type="checkbox" /> Toy car: 2</li>"#));
+        assert!(actual.contains(r#"<li><input type="checkbox" /> Lego brick: 230</li>"#));
     }
 
     #[test]
@@ -173,6 +1074,87 @@ mod test {
         assert_eq!(actual, "Toy car");
     }
 
+    #[tokio::test]
+    async fn test_manifest_content_type_matching() {
+        let json = r#"{
+            "package": {
+                "name": "not-a-gift-order",
+                "authors": ["Not Santa"],
+                "keywords": ["Christmas 2024"],
+                "metadata": { "orders": [{ "item": "Toy car", "quantity": 2 }] }
+            }
+        }"#;
+        for content_type in ["application/json; charset=utf-8", "Application/JSON"] {
+            let mut headers = HeaderMap::new();
+            headers.insert("Content-Type", content_type.parse().unwrap());
+            let response = super::manifest(
+                headers,
+                Extension(ManifestLimit::default()),
+                Query(Default::default()),
+                json.into(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(body_text(response).await, "Toy car: 2");
+        }
+
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "text/plain; charset=utf-8".parse().unwrap());
+        let response = super::manifest(
+            headers,
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            json.into(),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn test_manifest_toml_aliases() {
+        let toml = r#"
+            [package]
+            name = "not-a-gift-order"
+            authors = ["Not Santa"]
+            keywords = ["Christmas 2024"]
+
+            [[package.metadata.orders]]
+            item = "Toy car"
+            quantity = 2
+        "#;
+        for content_type in super::TOML_TYPES
+            .iter()
+            .chain(&["Text/TOML; charset=utf-8"])
+        {
+            let mut headers = HeaderMap::new();
+            headers.insert("Content-Type", content_type.parse().unwrap());
+            let response = super::manifest(
+                headers,
+                Extension(ManifestLimit::default()),
+                Query(Default::default()),
+                toml.into(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(body_text(response).await, "Toy car: 2", "{content_type}");
+        }
+
+        for content_type in ["application/x-yaml-toml", "text/x-toml-ish"] {
+            let mut headers = HeaderMap::new();
+            headers.insert("Content-Type", content_type.parse().unwrap());
+            let response = super::manifest(
+                headers,
+                Extension(ManifestLimit::default()),
+                Query(Default::default()),
+                toml.into(),
+            )
+            .await
+            .into_response();
+            assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        }
+    }
+
     #[tokio::test]
     async fn test_keyword_validation() {
         let toml = r#"
@@ -183,7 +1165,283 @@ mod test {
         "#;
         let mut headers = HeaderMap::new();
         headers.insert("Content-Type", "application/toml".parse().unwrap());
-        let actual = super::manifest(headers, toml.to_string()).await;
-        assert_eq!(actual, Err(super::ManifestError::NotChristmas));
+        let actual = super::manifest(
+            headers,
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            toml.into(),
+        )
+        .await;
+        assert_eq!(actual.unwrap_err(), super::ManifestError::NotChristmas);
+    }
+
+    #[tokio::test]
+    async fn test_schema() {
+        let Json(schema) = super::schema().await;
+        let validator = jsonschema::validator_for(&serde_json::to_value(schema).unwrap()).unwrap();
+
+        assert!(validator.is_valid(&json!({
+            "orders": [{ "item": "Toy car", "quantity": 2 }]
+        })));
+        assert!(!validator.is_valid(&json!({
+            "orders": [{ "item": "Toy car" }]
+        })));
+        assert!(!validator.is_valid(&json!({
+            "orders": [{ "item": "Toy car", "quantity": "two" }]
+        })));
+    }
+
+    #[tokio::test]
+    async fn test_validate() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "application/toml".parse().unwrap());
+        let validate = |toml: &str| {
+            super::validate(headers.clone(), Query(Default::default()), toml.to_string())
+        };
+
+        let toml = r#"
+            [package]
+            name = "not-a-gift-order"
+            authors = ["Not Santa"]
+            keywords = ["Christmas 2024"]
+        "#;
+        let response = validate(toml).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_text(response).await, r#"{"valid":true}"#);
+
+        let toml = r#"
+            [package]
+            name = "not-a-gift-order"
+            authors = ["Not Santa"]
+            keywords = ["Fartmas 2069"]
+        "#;
+        assert_eq!(
+            validate(toml).await.unwrap_err(),
+            ManifestError::NotChristmas
+        );
+
+        let response = validate("[package\nname = ").await.into_response();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn test_manifest_body_limit() {
+        use axum::extract::DefaultBodyLimit;
+
+        let app = Router::new().route(
+            "/manifest",
+            post(super::manifest).layer((DefaultBodyLimit::max(64), Extension(ManifestLimit(64)))),
+        );
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/manifest")
+                    .header(CONTENT_TYPE, "application/toml")
+                    .body(Body::from("#".repeat(65)))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_combine() {
+        let toml = r#"
+            [package]
+            name = "not-a-gift-order"
+            authors = ["Not Santa"]
+            keywords = ["Christmas 2024"]
+
+            [[package.metadata.orders]]
+            item = "Toy car"
+            quantity = 2
+        "#;
+        let json = r#"{
+            "package": {
+                "name": "not-a-gift-order",
+                "authors": ["Not Santa"],
+                "keywords": ["Christmas 2024"],
+                "metadata": {
+                    "orders": [{ "item": "Lego brick", "quantity": 230 }]
+                }
+            }
+        }"#;
+        let body = format!(
+            "--BOUNDARY\r\n\
+             Content-Disposition: form-data; name=\"first\"; filename=\"Cargo.toml\"\r\n\
+             Content-Type: application/toml\r\n\r\n\
+             {toml}\r\n\
+             --BOUNDARY\r\n\
+             Content-Disposition: form-data; name=\"second\"; filename=\"Cargo.json\"\r\n\
+             Content-Type: application/json\r\n\r\n\
+             {json}\r\n\
+             --BOUNDARY--\r\n"
+        );
+
+        let app = Router::new().route("/", post(super::combine));
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/")
+                    .header(CONTENT_TYPE, "multipart/form-data; boundary=BOUNDARY")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(body_text(response).await, "Toy car: 2\nLego brick: 230");
+    }
+
+    #[tokio::test]
+    async fn test_strict_tables() {
+        let toml = r#"
+            [package]
+            name = "not-a-gift-order"
+            authors = ["Not Santa"]
+            keywords = ["Christmas 2024"]
+
+            [[package.metadata.orders]]
+            item = "Toy car"
+            quantity = 2
+
+            [oops]
+            typo = true
+        "#;
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "application/toml".parse().unwrap());
+
+        let response = super::manifest(
+            headers.clone(),
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            toml.into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body_text(response).await, "Toy car: 2");
+
+        let strict = ManifestOpts {
+            strict_tables: true,
+            ..Default::default()
+        };
+        let actual = super::manifest(
+            headers,
+            Extension(ManifestLimit::default()),
+            Query(strict),
+            toml.into(),
+        )
+        .await;
+        assert_eq!(
+            actual.unwrap_err(),
+            ManifestError::UnknownTables(vec!["oops".to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verbose_rejected_orders() {
+        let toml = r#"
+            [package]
+            name = "not-a-gift-order"
+            authors = ["Not Santa"]
+            keywords = ["Christmas 2024"]
+
+            [[package.metadata.orders]]
+            item = "Toy car"
+            quantity = 2
+
+            [[package.metadata.orders]]
+            quantity = 5
+
+            [[package.metadata.orders]]
+            item = "Lego brick"
+
+            [[package.metadata.orders]]
+            item = "Teddy bear"
+            quantity = "lots"
+        "#;
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "application/toml".parse().unwrap());
+
+        let response = super::manifest(
+            headers.clone(),
+            Extension(ManifestLimit::default()),
+            Query(Default::default()),
+            toml.into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body_text(response).await, "Toy car: 2");
+
+        let verbose = ManifestOpts {
+            verbose: true,
+            ..Default::default()
+        };
+        let actual = super::manifest(
+            headers,
+            Extension(ManifestLimit::default()),
+            Query(verbose),
+            toml.into(),
+        )
+        .await;
+        let expected = vec![
+            RejectedOrder {
+                index: 1,
+                reason: "missing item",
+            },
+            RejectedOrder {
+                index: 2,
+                reason: "missing quantity",
+            },
+            RejectedOrder {
+                index: 3,
+                reason: "quantity must be an integer",
+            },
+        ];
+        let error = actual.unwrap_err();
+        assert_eq!(error, ManifestError::RejectedOrders(expected));
+
+        let response = error.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(body["rejected"][2]["index"], 3);
+        assert_eq!(body["rejected"][2]["reason"], "quantity must be an integer");
+    }
+
+    proptest! {
+        #[test]
+        fn parse_manifest_never_panics(
+            content_type in prop_oneof![
+                Just("application/toml"),
+                Just("application/json"),
+                Just("application/yaml"),
+                Just("text/plain"),
+            ],
+            body in r#"[\[\]{}a-z0-9 ="',.:\n-]{0,256}"#,
+        ) {
+            let _ = parse_manifest(content_type, &body);
+        }
+
+        #[test]
+        fn parse_manifest_round_trips(
+            orders in prop::collection::vec(("[A-Za-z][A-Za-z ]{0,15}", any::<i64>()), 1..8)
+        ) {
+            let mut toml = String::from(
+                "[package]\nname = \"fuzz\"\nkeywords = [\"Christmas 2024\"]\n",
+            );
+            for (item, quantity) in &orders {
+                toml.push_str(&format!(
+                    "[[package.metadata.orders]]\nitem = \"{item}\"\nquantity = {quantity}\n"
+                ));
+            }
+            let expected: Vec<Order> = orders
+                .into_iter()
+                .map(|(item, quantity)| Order { item, quantity })
+                .collect();
+            prop_assert_eq!(parse_manifest("application/toml", &toml).unwrap(), expected);
+        }
     }
 }
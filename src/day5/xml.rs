@@ -0,0 +1,114 @@
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use thiserror::Error;
+use toml::{Table, Value};
+
+// Elements that are lists even when only one of them is present
+const LIST_ELEMENTS: &[&str] = &["authors", "keywords", "orders"];
+
+#[derive(Error, Debug)]
+pub enum XmlError {
+    #[error(transparent)]
+    Xml(#[from] quick_xml::Error),
+    #[error("document ended inside <{0}>")]
+    Unclosed(String),
+    #[error("document has no root element")]
+    NoRoot,
+}
+
+#[derive(Default)]
+struct Element {
+    name: String,
+    children: Table,
+    text: String,
+}
+
+impl Element {
+    fn start(tag: &BytesStart) -> Result<Self, XmlError> {
+        let mut element = Element {
+            name: String::from_utf8_lossy(tag.local_name().as_ref()).into_owned(),
+            ..Default::default()
+        };
+        for attribute in tag.attributes() {
+            let attribute = attribute.map_err(quick_xml::Error::from)?;
+            let key = String::from_utf8_lossy(attribute.key.local_name().as_ref()).into_owned();
+            let value = attribute.unescape_value()?.into_owned();
+            element.insert(key, Value::String(value));
+        }
+        Ok(element)
+    }
+
+    // Repeated siblings collect into an array in document order
+    fn insert(&mut self, key: String, value: Value) {
+        match self.children.get_mut(&key) {
+            Some(Value::Array(values)) => values.push(value),
+            Some(existing) => {
+                let first = std::mem::replace(existing, Value::Boolean(false));
+                *existing = Value::Array(vec![first, value]);
+            }
+            None if LIST_ELEMENTS.contains(&key.as_str()) => {
+                self.children.insert(key, Value::Array(vec![value]));
+            }
+            None => {
+                self.children.insert(key, value);
+            }
+        }
+    }
+
+    // XML has no types of its own, so leaves stay strings. The one exception is
+    // quantity, and one that isn't a whole number is left as text for the order
+    // validation to reject.
+    fn into_value(self) -> Value {
+        if !self.children.is_empty() {
+            return Value::Table(self.children);
+        }
+        let text = self.text.trim();
+        match text.parse() {
+            Ok(quantity) if self.name == "quantity" => Value::Integer(quantity),
+            _ => Value::String(text.to_string()),
+        }
+    }
+}
+
+// Builds the TOML table for the children of the root element, whatever it is called
+pub fn to_table(xml: &str) -> Result<Table, XmlError> {
+    let mut reader = Reader::from_str(xml);
+    let mut stack: Vec<Element> = Vec::new();
+    loop {
+        let element = match reader.read_event()? {
+            Event::Start(tag) => {
+                stack.push(Element::start(&tag)?);
+                continue;
+            }
+            Event::Empty(tag) => Element::start(&tag)?,
+            Event::End(_) => stack.pop().ok_or(XmlError::NoRoot)?,
+            Event::Text(text) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&text.unescape()?);
+                }
+                continue;
+            }
+            Event::CData(data) => {
+                if let Some(element) = stack.last_mut() {
+                    element
+                        .text
+                        .push_str(&data.decode().map_err(quick_xml::Error::from)?);
+                }
+                continue;
+            }
+            Event::Eof => {
+                return Err(match stack.pop() {
+                    Some(element) => XmlError::Unclosed(element.name),
+                    None => XmlError::NoRoot,
+                })
+            }
+            _ => continue,
+        };
+        match stack.last_mut() {
+            Some(parent) => parent.insert(element.name.clone(), element.into_value()),
+            None => return Ok(element.children),
+        }
+    }
+}
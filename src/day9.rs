@@ -1,71 +1,342 @@
 use std::{
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
     time::Duration,
 };
 
 use axum::{
-    extract::{rejection::JsonRejection, State},
-    http::StatusCode,
+    extract::{rejection::JsonRejection, FromRef, State},
+    http::{header, StatusCode},
     response::IntoResponse,
-    routing::post,
+    routing::{get, post},
     Json,
 };
 use leaky_bucket::RateLimiter;
 use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+use utoipa::{OpenApi, ToSchema};
 
-type LimiterState = Arc<Mutex<RateLimiter>>;
+const RATE_LIMIT_REMAINING: &str = "x-ratelimit-remaining";
 
-pub fn router() -> axum::Router {
+pub type LimiterState = Arc<Mutex<MilkBucket>>;
+type MetricsState = Arc<MilkMetrics>;
+
+#[derive(Default)]
+pub struct MilkMetrics {
+    requests: AtomicU64,
+    withdrawals: AtomicU64,
+    rejections: AtomicU64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RateLimiterConfig {
+    pub max: usize,
+    pub initial: usize,
+    pub interval_ms: u64,
+}
+
+impl Default for RateLimiterConfig {
+    fn default() -> Self {
+        Self {
+            max: 5,
+            initial: 5,
+            interval_ms: 1000,
+        }
+    }
+}
+
+impl RateLimiterConfig {
+    pub fn build(&self) -> MilkBucket {
+        MilkBucket {
+            limiter: RateLimiter::builder()
+                .max(self.max)
+                .initial(self.initial)
+                .interval(Duration::from_millis(self.interval_ms))
+                .build(),
+            last_acquire: Instant::now(),
+        }
+    }
+}
+
+// The limiter only tops its balance up when someone acquires, so the time of the
+// last attempt is kept next to it to work out what has dripped in since
+pub struct MilkBucket {
+    limiter: RateLimiter,
+    last_acquire: Instant,
+}
+
+impl MilkBucket {
+    fn try_acquire(&mut self, permits: usize) -> bool {
+        self.last_acquire = Instant::now();
+        self.limiter.try_acquire(permits)
+    }
+
+    fn balance(&self) -> usize {
+        self.limiter.balance()
+    }
+
+    // The limiter's own refill is never further off than the last attempt plus one
+    // interval, so this can lag behind it but never promises milk it won't give
+    fn available(&self, config: &RateLimiterConfig) -> usize {
+        let elapsed = self.last_acquire.elapsed().as_millis();
+        let refilled = elapsed / u128::from(config.interval_ms.max(1));
+        let refilled = usize::try_from(refilled).unwrap_or(usize::MAX);
+        self.balance().saturating_add(refilled).min(config.max)
+    }
+}
+
+#[derive(Clone)]
+struct AppState {
+    limiter: LimiterState,
+    metrics: MetricsState,
+    config: RateLimiterConfig,
+}
+
+impl FromRef<AppState> for LimiterState {
+    fn from_ref(input: &AppState) -> Self {
+        input.limiter.clone()
+    }
+}
+
+impl FromRef<AppState> for MetricsState {
+    fn from_ref(input: &AppState) -> Self {
+        input.metrics.clone()
+    }
+}
+
+impl FromRef<AppState> for RateLimiterConfig {
+    fn from_ref(input: &AppState) -> Self {
+        input.config
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(milk, milk_convert, refill, weight, metrics, status))]
+pub struct ApiDoc;
+
+pub fn router_with_limiter(config: RateLimiterConfig, limiter: LimiterState) -> axum::Router {
     axum::Router::new()
         .route("/milk", post(milk))
+        .route("/convert", post(milk_convert))
         .route("/refill", post(refill))
-        .with_state(Arc::new(Mutex::new(
-            RateLimiter::builder()
-                .max(5)
-                .initial(5)
-                .interval(Duration::from_secs(1))
-                .build(),
-        )))
+        .route("/weight", post(weight))
+        .route("/metrics", get(metrics))
+        .route("/status", get(status))
+        .with_state(AppState {
+            limiter,
+            metrics: MetricsState::default(),
+            config,
+        })
 }
 
+#[utoipa::path(
+    post,
+    path = "/milk",
+    request_body(content = Option<Object>, description = "A single unit to convert, e.g. {\"liters\": 1.0}"),
+    responses(
+        (status = OK),
+        (status = BAD_REQUEST),
+        (status = TOO_MANY_REQUESTS),
+    ),
+)]
 pub async fn milk(
     State(state): State<LimiterState>,
+    State(metrics): State<MetricsState>,
+    State(config): State<RateLimiterConfig>,
+    quantity: Result<Json<MilkRequest>, JsonRejection>,
+) -> axum::response::Response {
+    let mut rate_limiter = state.lock().unwrap();
+    metrics.requests.fetch_add(1, Ordering::Relaxed);
+    if rate_limiter.try_acquire(1) {
+        metrics.withdrawals.fetch_add(1, Ordering::Relaxed);
+        let response = match quantity {
+            Ok(Json(request)) => match convert(request) {
+                Some(converted) => Json(converted).into_response(),
+                None => StatusCode::BAD_REQUEST.into_response(),
+            },
+            Err(JsonRejection::MissingJsonContentType(_)) => "Milk withdrawn\n".into_response(),
+            _ => StatusCode::BAD_REQUEST.into_response(),
+        };
+        let remaining = rate_limiter.balance().to_string();
+        ([(RATE_LIMIT_REMAINING, remaining)], response).into_response()
+    } else {
+        metrics.rejections.fetch_add(1, Ordering::Relaxed);
+        // The next unit drips in within one interval, rounded up to whole seconds
+        let retry_after = config.interval_ms.div_ceil(1000).to_string();
+        (
+            StatusCode::TOO_MANY_REQUESTS,
+            [
+                (RATE_LIMIT_REMAINING, "0".to_string()),
+                (header::RETRY_AFTER.as_str(), retry_after),
+            ],
+            "No milk available\n",
+        )
+            .into_response()
+    }
+}
+
+// Same conversion as /milk without touching the bucket, for internal tooling
+#[utoipa::path(
+    post,
+    path = "/convert",
+    request_body(content = Object, description = "A single unit to convert, e.g. {\"liters\": 1.0}"),
+    responses(
+        (status = OK),
+        (status = BAD_REQUEST),
+    ),
+)]
+pub async fn milk_convert(
     quantity: Result<Json<MilkRequest>, JsonRejection>,
 ) -> axum::response::Response {
-    let rate_limiter = &state.lock().unwrap();
+    match quantity.ok().and_then(|Json(request)| convert(request)) {
+        Some(converted) => Json(converted).into_response(),
+        None => StatusCode::BAD_REQUEST.into_response(),
+    }
+}
+
+fn convert(req: MilkRequest) -> Option<MilkRequest> {
+    let converted = match req {
+        MilkRequest::Gallons { gallons } => MilkRequest::Liters {
+            liters: gallons * 3.7854111,
+        },
+        MilkRequest::Liters { liters } => MilkRequest::Gallons {
+            gallons: liters / 3.7854111,
+        },
+        MilkRequest::Pints { pints } => MilkRequest::Litres {
+            litres: pints / 1.75975,
+        },
+        MilkRequest::Litres { litres } => MilkRequest::Pints {
+            pints: litres * 1.75975,
+        },
+        // liters already converts to gallons, so cups only go one way
+        MilkRequest::Cups { cups } => MilkRequest::Liters {
+            liters: cups * 0.2365882365,
+        },
+        MilkRequest::Tablespoons { tablespoons } => MilkRequest::Milliliters {
+            milliliters: tablespoons * 14.78676478125,
+        },
+        MilkRequest::Milliliters { milliliters } => MilkRequest::Tablespoons {
+            tablespoons: milliliters / 14.78676478125,
+        },
+    };
+    Some(converted)
+}
+
+#[utoipa::path(
+    post,
+    path = "/weight",
+    request_body = Object,
+    responses(
+        (status = OK),
+        (status = BAD_REQUEST),
+        (status = TOO_MANY_REQUESTS),
+    ),
+)]
+// Weighing draws from the same bucket as /milk, so either endpoint can exhaust the other
+pub async fn weight(
+    State(state): State<LimiterState>,
+    quantity: Result<Json<WeightRequest>, JsonRejection>,
+) -> axum::response::Response {
+    let mut rate_limiter = state.lock().unwrap();
     if rate_limiter.try_acquire(1) {
         match quantity {
-            Ok(Json(MilkRequest::Gallons { gallons })) => Json(MilkRequest::Liters {
-                liters: gallons * 3.7854111,
+            Ok(Json(WeightRequest::Kilograms { kilograms })) => Json(WeightRequest::Pounds {
+                pounds: kilograms * 2.20462262,
             })
             .into_response(),
-            Ok(Json(MilkRequest::Liters { liters })) => Json(MilkRequest::Gallons {
-                gallons: liters / 3.7854111,
+            Ok(Json(WeightRequest::Pounds { pounds })) => Json(WeightRequest::Kilograms {
+                kilograms: pounds / 2.20462262,
             })
             .into_response(),
-            Ok(Json(MilkRequest::Pints { pints })) => Json(MilkRequest::Litres {
-                litres: pints / 1.75975,
+            Ok(Json(WeightRequest::Grams { grams })) => Json(WeightRequest::Ounces {
+                ounces: grams / 28.349523125,
             })
             .into_response(),
-            Ok(Json(MilkRequest::Litres { litres })) => Json(MilkRequest::Pints {
-                pints: litres * 1.75975,
+            Ok(Json(WeightRequest::Ounces { ounces })) => Json(WeightRequest::Grams {
+                grams: ounces * 28.349523125,
             })
             .into_response(),
-            Err(JsonRejection::MissingJsonContentType(_)) => "Milk withdrawn\n".into_response(),
             _ => StatusCode::BAD_REQUEST.into_response(),
         }
     } else {
-        (StatusCode::TOO_MANY_REQUESTS, "No milk available\n").into_response()
+        (
+            StatusCode::TOO_MANY_REQUESTS,
+            "Scale is busy, try again later\n",
+        )
+            .into_response()
     }
 }
 
-pub async fn refill(State(state): State<LimiterState>) -> axum::response::Response {
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses((status = OK, body = String, content_type = "text/plain")),
+)]
+pub async fn metrics(State(metrics): State<MetricsState>) -> axum::response::Response {
+    let counters = [
+        (
+            "milk_requests_total",
+            "Total milk requests",
+            &metrics.requests,
+        ),
+        (
+            "milk_withdrawals_total",
+            "Milk requests that withdrew from the bucket",
+            &metrics.withdrawals,
+        ),
+        (
+            "milk_rejections_total",
+            "Milk requests rejected with 429",
+            &metrics.rejections,
+        ),
+    ];
+    let body: String = counters
+        .into_iter()
+        .map(|(name, help, counter)| {
+            format!(
+                "# HELP {name} {help}\n# TYPE {name} counter\n{name} {}\n",
+                counter.load(Ordering::Relaxed)
+            )
+        })
+        .collect();
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response()
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct MilkStatus {
+    available: u32,
+    max: u32,
+}
+
+#[utoipa::path(
+    get,
+    path = "/status",
+    responses((status = OK, body = MilkStatus)),
+)]
+pub async fn status(
+    State(state): State<LimiterState>,
+    State(config): State<RateLimiterConfig>,
+) -> Json<MilkStatus> {
+    let available = state.lock().unwrap().available(&config);
+    Json(MilkStatus {
+        available: available as u32,
+        max: config.max as u32,
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/refill",
+    responses((status = OK)),
+)]
+pub async fn refill(
+    State(state): State<LimiterState>,
+    State(config): State<RateLimiterConfig>,
+) -> axum::response::Response {
     let mut rate_limiter = state.lock().unwrap();
-    *rate_limiter = RateLimiter::builder()
-        .max(5)
-        .initial(5)
-        .interval(Duration::from_secs(1))
-        .build();
+    *rate_limiter = config.build();
     StatusCode::OK.into_response()
 }
 
@@ -76,4 +347,389 @@ pub enum MilkRequest {
     Liters { liters: f64 },
     Pints { pints: f64 },
     Litres { litres: f64 },
+    Cups { cups: f64 },
+    Tablespoons { tablespoons: f64 },
+    Milliliters { milliliters: f64 },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum WeightRequest {
+    Kilograms { kilograms: f64 },
+    Pounds { pounds: f64 },
+    Grams { grams: f64 },
+    Ounces { ounces: f64 },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{body::Body, extract::Request};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    fn router(config: RateLimiterConfig) -> axum::Router {
+        router_with_limiter(config, Arc::new(Mutex::new(config.build())))
+    }
+
+    fn limiter() -> LimiterState {
+        Arc::new(Mutex::new(RateLimiterConfig::default().build()))
+    }
+
+    async fn convert_weight(state: LimiterState, request: WeightRequest) -> WeightRequest {
+        let response = weight(State(state), Ok(Json(request))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_weight_round_trip() {
+        let state = limiter();
+
+        let WeightRequest::Pounds { pounds } =
+            convert_weight(state.clone(), WeightRequest::Kilograms { kilograms: 12.5 }).await
+        else {
+            panic!("kilograms should convert to pounds");
+        };
+        assert!((pounds - 27.5577828).abs() < 1e-6);
+
+        let WeightRequest::Kilograms { kilograms } =
+            convert_weight(state.clone(), WeightRequest::Pounds { pounds }).await
+        else {
+            panic!("pounds should convert to kilograms");
+        };
+        assert!((kilograms - 12.5).abs() < 1e-9);
+
+        let WeightRequest::Grams { grams } =
+            convert_weight(state, WeightRequest::Ounces { ounces: 16.0 }).await
+        else {
+            panic!("ounces should convert to grams");
+        };
+        assert!((grams - 453.59237).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_weight_rate_limited() {
+        let state = limiter();
+        for _ in 0..5 {
+            convert_milk(state.clone(), MilkRequest::Liters { liters: 1.0 }).await;
+        }
+        let response = weight(
+            State(state),
+            Ok(Json(WeightRequest::Kilograms { kilograms: 1.0 })),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "Scale is busy, try again later\n");
+    }
+
+    async fn convert_milk(state: LimiterState, request: MilkRequest) -> MilkRequest {
+        let response = milk(
+            State(state),
+            State(Default::default()),
+            State(Default::default()),
+            Ok(Json(request)),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_milk_cups_and_tablespoons() {
+        let state = limiter();
+
+        let MilkRequest::Liters { liters } =
+            convert_milk(state.clone(), MilkRequest::Cups { cups: 16.0 }).await
+        else {
+            panic!("cups should convert to liters");
+        };
+        let MilkRequest::Gallons { gallons } =
+            convert_milk(state.clone(), MilkRequest::Liters { liters }).await
+        else {
+            panic!("liters should convert to gallons");
+        };
+        assert!((gallons - 1.0).abs() < 1e-6);
+
+        let MilkRequest::Milliliters { milliliters } =
+            convert_milk(state.clone(), MilkRequest::Tablespoons { tablespoons: 3.0 }).await
+        else {
+            panic!("tablespoons should convert to milliliters");
+        };
+        assert!((milliliters - 44.36029434375).abs() < 1e-9);
+
+        let MilkRequest::Tablespoons { tablespoons } =
+            convert_milk(state, MilkRequest::Milliliters { milliliters }).await
+        else {
+            panic!("milliliters should convert to tablespoons");
+        };
+        assert!((tablespoons - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_milk_units_unambiguous() {
+        let cups: MilkRequest = serde_json::from_str(r#"{"cups": 2.0}"#).unwrap();
+        assert!(matches!(cups, MilkRequest::Cups { cups } if cups == 2.0));
+        assert!(serde_json::from_str::<MilkRequest>(r#"{"cups": 2.0, "liters": 1.0}"#).is_err());
+        assert!(
+            serde_json::from_str::<MilkRequest>(r#"{"tablespoons": 1.0, "milliliters": 1.0}"#)
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_config_max() {
+        let app = router(RateLimiterConfig {
+            max: 2,
+            initial: 2,
+            interval_ms: 60_000,
+        });
+        let milk = || {
+            Request::builder()
+                .method("POST")
+                .uri("/milk")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        for _ in 0..2 {
+            let response = app.clone().oneshot(milk()).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let response = app.clone().oneshot(milk()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let refill = Request::builder()
+            .method("POST")
+            .uri("/refill")
+            .body(Body::empty())
+            .unwrap();
+        app.clone().oneshot(refill).await.unwrap();
+        let response = app.oneshot(milk()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let app = router(RateLimiterConfig {
+            max: 2,
+            initial: 2,
+            interval_ms: 60_000,
+        });
+        for _ in 0..5 {
+            app.clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/milk")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+        }
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/metrics")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("\nmilk_requests_total 5\n"));
+        assert!(body.contains("\nmilk_withdrawals_total 2\n"));
+        assert!(body.contains("\nmilk_rejections_total 3\n"));
+    }
+
+    #[tokio::test]
+    async fn test_status() {
+        let app = router(RateLimiterConfig {
+            max: 5,
+            initial: 5,
+            interval_ms: 60_000,
+        });
+        async fn status(app: &axum::Router) -> MilkStatus {
+            let request = Request::builder()
+                .uri("/status")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            serde_json::from_slice(&body).unwrap()
+        }
+        async fn post(app: &axum::Router, uri: &str) {
+            let request = Request::builder()
+                .method("POST")
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap();
+            app.clone().oneshot(request).await.unwrap();
+        }
+
+        let full = status(&app).await;
+        assert_eq!((full.available, full.max), (5, 5));
+
+        for _ in 0..3 {
+            post(&app, "/milk").await;
+        }
+        let drained = status(&app).await;
+        assert_eq!((drained.available, drained.max), (2, 5));
+
+        post(&app, "/refill").await;
+        let refilled = status(&app).await;
+        assert_eq!((refilled.available, refilled.max), (5, 5));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_status_refills_over_time() {
+        let app = router(RateLimiterConfig {
+            max: 5,
+            initial: 5,
+            interval_ms: 500,
+        });
+        for _ in 0..3 {
+            let request = Request::builder()
+                .method("POST")
+                .uri("/milk")
+                .body(Body::empty())
+                .unwrap();
+            app.clone().oneshot(request).await.unwrap();
+        }
+
+        let status = |app: axum::Router| async move {
+            let request = Request::builder()
+                .uri("/status")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            serde_json::from_slice::<MilkStatus>(&body)
+                .unwrap()
+                .available
+        };
+
+        tokio::time::advance(Duration::from_millis(600)).await;
+        assert_eq!(status(app.clone()).await, 3);
+        tokio::time::advance(Duration::from_millis(500)).await;
+        assert_eq!(status(app.clone()).await, 4);
+        tokio::time::advance(Duration::from_secs(60)).await;
+        assert_eq!(status(app.clone()).await, 5);
+
+        // Checking the status doesn't take anything out of the bucket
+        assert_eq!(status(app).await, 5);
+    }
+
+    #[tokio::test]
+    async fn test_milk_rate_limit_headers() {
+        let state = limiter();
+        let mut remaining = Vec::new();
+        for _ in 0..5 {
+            let response = milk(
+                State(state.clone()),
+                State(Default::default()),
+                State(Default::default()),
+                Ok(Json(MilkRequest::Liters { liters: 1.0 })),
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let header = response.headers()[RATE_LIMIT_REMAINING].to_str().unwrap();
+            remaining.push(header.parse::<usize>().unwrap());
+        }
+        assert_eq!(remaining, [4, 3, 2, 1, 0]);
+
+        let response = milk(
+            State(state),
+            State(Default::default()),
+            State(Default::default()),
+            Ok(Json(MilkRequest::Liters { liters: 1.0 })),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[RATE_LIMIT_REMAINING], "0");
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+    }
+
+    #[tokio::test]
+    async fn test_milk_retry_after_interval() {
+        let config = RateLimiterConfig {
+            max: 1,
+            initial: 0,
+            interval_ms: 2500,
+        };
+        let response = milk(
+            State(Arc::new(Mutex::new(config.build()))),
+            State(Default::default()),
+            State(config),
+            Ok(Json(MilkRequest::Liters { liters: 1.0 })),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "3");
+    }
+
+    #[tokio::test]
+    async fn test_convert_bypasses_limiter() {
+        let app = router(RateLimiterConfig {
+            max: 1,
+            initial: 1,
+            interval_ms: 60_000,
+        });
+        let post = |uri: &str, body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(post("/milk", r#"{"liters": 1.0}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app
+            .clone()
+            .oneshot(post("/milk", r#"{"liters": 1.0}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        for _ in 0..3 {
+            let response = app
+                .clone()
+                .oneshot(post("/convert", r#"{"gallons": 1.0}"#))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let MilkRequest::Liters { liters } = serde_json::from_slice(&body).unwrap() else {
+                panic!("gallons should convert to liters");
+            };
+            assert!((liters - 3.7854111).abs() < 1e-9);
+        }
+
+        let response = app
+            .clone()
+            .oneshot(post("/convert", r#"{"gallons": 1.0, "liters": 1.0}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app
+            .oneshot(post("/milk", r#"{"liters": 1.0}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
 }
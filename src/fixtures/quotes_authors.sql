@@ -0,0 +1,9 @@
+INSERT INTO quotes (id, author, quote, created_at)
+VALUES
+('0b7e5a52-6f3e-4c1a-9d2e-1a2b3c4d5e01', 'LUCIFER', 'It is better to rule in Hell than to serve in Heaven.', '2024-12-01T00:00:00Z'),
+('0b7e5a52-6f3e-4c1a-9d2e-1a2b3c4d5e02', 'BAAL', 'I have walked the earth, always searching for that which will make me whole.', '2024-12-02T00:00:00Z'),
+('0b7e5a52-6f3e-4c1a-9d2e-1a2b3c4d5e03', 'LUCIFER', 'Better to reign in Hell.', '2024-12-03T00:00:00Z'),
+('0b7e5a52-6f3e-4c1a-9d2e-1a2b3c4d5e04', 'BAAL', 'Whole at last.', '2024-12-04T00:00:00Z'),
+('0b7e5a52-6f3e-4c1a-9d2e-1a2b3c4d5e05', 'LUCIFER', 'The mind is its own place.', '2024-12-05T00:00:00Z'),
+('0b7e5a52-6f3e-4c1a-9d2e-1a2b3c4d5e06', 'LUCIFER', 'Awake, arise, or be for ever fallen.', '2024-12-06T00:00:00Z'),
+('0b7e5a52-6f3e-4c1a-9d2e-1a2b3c4d5e07', 'ASMODEUS', 'Hi I am Asmodeus.', '2024-12-07T00:00:00Z');
\ No newline at end of file
@@ -0,0 +1,7 @@
+INSERT INTO quotes (id, author, quote, created_at, likes)
+VALUES
+('fa7a08a9-f649-4c05-917a-4b989cb25701', 'BEELZEBUB', 'Go inquire of Baal-zebub, the god of Ekron, whether I shall recover from this injury.', '2024-12-01T00:00:00Z', 2),
+('f7b3b1b4-3b7b-4b3b-8b3b-3b7b3b7b3b7b', 'LUCIFER', 'It is better to rule in Hell than to serve in Heaven.', '2024-12-02T00:00:00Z', 10),
+('f7b3b1b4-3b7b-4b3b-8b3b-3b7b3b7b3555', 'BAAL', 'I have walked the earth, always searching for that which will make me whole.', '2024-12-03T00:00:00Z', 0),
+('f7b3b1b4-3b7b-4b3b-8b3b-3b7b3b7b7776', 'BOB', 'Hi I am Bob.', '2024-12-04T00:00:00Z', 7),
+('f7b3b1b4-3b7b-4b3b-8b3b-3b7b3b7b7777', 'ALICE', 'Hi I am Alice.', '2024-12-05T00:00:00Z', 3);
\ No newline at end of file
@@ -0,0 +1,6 @@
+INSERT INTO quotes (id, author, quote, created_at)
+VALUES
+('f7b3b1b4-3b7b-4b3b-8b3b-3b7b3b7b3b7b', 'LUCIFER', 'It is better to rule in Hell than to serve in Heaven.', '2024-12-01T00:00:00Z'),
+('f7b3b1b4-3b7b-4b3b-8b3b-3b7b3b7b3555', 'BAAL', 'I have walked the earth, always searching for that which will make me whole.', '2024-12-02T00:00:00Z'),
+('f7b3b1b4-3b7b-4b3b-8b3b-3b7b3b7b7776', 'GABRIEL', 'Heaven, heaven, glorious heaven above.', '2024-12-03T00:00:00Z'),
+('f7b3b1b4-3b7b-4b3b-8b3b-3b7b3b7b7777', 'BOB', 'Hi I am Bob.', '2024-12-04T00:00:00Z');
\ No newline at end of file
@@ -0,0 +1,44 @@
+use axum::{extract::State, http::StatusCode, routing::get};
+use sqlx::PgPool;
+use tracing::error;
+
+pub fn router(pool: PgPool) -> axum::Router {
+    axum::Router::new().route("/", get(health)).with_state(pool)
+}
+
+async fn health(State(state): State<PgPool>) -> (StatusCode, &'static str) {
+    match sqlx::query!("SELECT 1 AS one").fetch_one(&state).await {
+        Ok(_) => (StatusCode::OK, "ok"),
+        Err(err) => {
+            error!(%err, "database health check failed");
+            (StatusCode::SERVICE_UNAVAILABLE, "db unavailable")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{body::Body, extract::Request};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    #[sqlx::test]
+    async fn test_health(pool: PgPool) {
+        let app = axum::Router::new().nest("/health", router(pool));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/health")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "ok");
+    }
+}
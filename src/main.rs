@@ -1,10 +1,17 @@
+use std::sync::{Arc, Mutex};
+
 use axum::{
+    extract::DefaultBodyLimit,
+    middleware,
     routing::{get, post},
-    Router,
+    Extension, Json, Router,
 };
 use axum_embed::ServeEmbed;
 use rust_embed::RustEmbed;
+use utoipa::OpenApi;
 
+mod admin;
+mod auth;
 mod day0;
 mod day12;
 mod day16;
@@ -13,6 +20,8 @@ mod day2;
 mod day23;
 mod day5;
 mod day9;
+mod health;
+mod request_id;
 
 #[shuttle_runtime::main]
 async fn main(
@@ -24,20 +33,54 @@ async fn main(
         .await
         .expect("Failed to run migrations");
 
+    let board = day12::shared_board(&pool).await;
+    let milk_config = day9::RateLimiterConfig::default();
+    let milk = Arc::new(Mutex::new(milk_config.build()));
+    let manifest_limit = std::env::var("MANIFEST_BODY_LIMIT")
+        .ok()
+        .and_then(|limit| limit.parse().ok())
+        .unwrap_or(day5::MANIFEST_BODY_LIMIT);
+    let admin = admin::AdminHandles {
+        board: board.clone(),
+        pool: pool.clone(),
+        milk: milk.clone(),
+        milk_config,
+    };
+
     let router = Router::new()
         .route("/", get(day0::hello_bird))
         .route("/-1/seek", get(day0::the_word))
-        .route("/2/dest", get(day2::dest))
+        .nest("/health", health::router(pool.clone()))
+        .route("/2/dest", get(day2::dest).post(day2::dest))
+        .route("/2/dest/batch", post(day2::dest_batch))
         .route("/2/key", get(day2::key))
+        .route("/2/key/network", get(day2::key_network))
+        .route("/2/subnet", get(day2::subnet))
+        .route("/2/mid", get(day2::mid))
         .route("/2/v6/dest", get(day2::dest_v6))
+        .route("/2/auto/dest", get(day2::dest_auto))
         .route("/2/v6/key", get(day2::key_v6))
-        .route("/5/manifest", post(day5::manifest))
-        .nest("/9", day9::router())
-        .nest("/12", day12::router())
+        .route("/2/v6/mid", get(day2::mid_v6))
+        .route(
+            "/5/manifest",
+            post(day5::manifest).layer((
+                DefaultBodyLimit::max(manifest_limit),
+                Extension(day5::ManifestLimit(manifest_limit)),
+            )),
+        )
+        .route("/5/manifest/combine", post(day5::combine))
+        .route("/5/validate", post(day5::validate))
+        .route("/5/schema", get(day5::schema))
+        .nest("/9", day9::router_with_limiter(milk_config, milk))
+        .nest("/12", day12::router_with_board(pool.clone(), board))
         .nest("/16", day16::router())
         .nest("/19", day19::router(pool))
         .nest("/23", day23::router())
-        .nest_service("/assets", ServeEmbed::<Assets>::new());
+        .nest("/admin", admin::router(admin))
+        .route("/assets/index.json", get(assets_index))
+        .route("/openapi.json", get(openapi))
+        .nest_service("/assets", ServeEmbed::<Assets>::new())
+        .layer(middleware::from_fn(request_id::request_id));
 
     Ok(router.into())
 }
@@ -45,3 +88,57 @@ async fn main(
 #[derive(RustEmbed, Clone)]
 #[folder = "assets/"]
 struct Assets;
+
+async fn assets_index() -> Json<Vec<String>> {
+    Json(Assets::iter().map(|path| path.into_owned()).collect())
+}
+
+#[derive(OpenApi)]
+#[openapi(nest(
+    (path = "/2", api = day2::ApiDoc),
+    (path = "/5", api = day5::ApiDoc),
+    (path = "/9", api = day9::ApiDoc),
+    (path = "/19", api = day19::ApiDoc),
+))]
+struct ApiDoc;
+
+async fn openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{body::Body, extract::Request};
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_assets_index() {
+        let Json(paths) = assets_index().await;
+        assert!(paths.contains(&"23.html".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_openapi() {
+        let app = Router::new().route("/openapi.json", get(openapi));
+        let request = Request::builder()
+            .uri("/openapi.json")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let doc: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        let paths = &doc["paths"];
+        assert!(paths["/19/list"]["get"].is_object());
+        assert!(paths["/19/draft"]["post"].is_object());
+        assert!(paths["/19/cite/{id}"]["get"].is_object());
+        assert!(paths["/2/dest"]["get"].is_object());
+        assert!(paths["/2/dest"]["post"].is_object());
+        assert!(paths["/5/manifest"]["post"].is_object());
+        assert!(paths["/9/milk"]["post"].is_object());
+        assert!(paths["/9/milk"]["get"].is_null());
+    }
+}
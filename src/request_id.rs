@@ -0,0 +1,53 @@
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::{info_span, Instrument};
+use uuid::Uuid;
+
+static X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+// Tags every request with a fresh id so interleaved log lines can be told apart,
+// handler spans nest under this one
+pub async fn request_id(request: Request, next: Next) -> Response {
+    let id = Uuid::new_v4().to_string();
+    let span = info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        uri = %request.uri(),
+    );
+    let mut response = next.run(request).instrument(span).await;
+    let id = HeaderValue::from_str(&id).expect("uuids are valid header values");
+    response.headers_mut().insert(X_REQUEST_ID.clone(), id);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_request_id() {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(middleware::from_fn(request_id));
+
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let id = response.headers()[&X_REQUEST_ID].to_str().unwrap();
+            ids.push(Uuid::parse_str(id).unwrap());
+        }
+        assert_ne!(ids[0], ids[1]);
+    }
+}